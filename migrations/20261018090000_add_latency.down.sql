@@ -0,0 +1,2 @@
+ALTER TABLE status_history DROP COLUMN latency_ms;
+ALTER TABLE status_history DROP COLUMN ttfb_ms;
@@ -0,0 +1,3 @@
+-- total request duration and time to the response headers, NULL for failed checks
+ALTER TABLE status_history ADD COLUMN latency_ms INTEGER NULL;
+ALTER TABLE status_history ADD COLUMN ttfb_ms INTEGER NULL;
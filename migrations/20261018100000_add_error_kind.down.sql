@@ -0,0 +1,21 @@
+CREATE TABLE status_history_new (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    status_id INTEGER NOT NULL,
+    -- -1 for network failure
+    status_code INTEGER NOT NULL,
+    created DATETIME DEFAULT CURRENT_TIMESTAMP,
+    latency_ms INTEGER NULL,
+    ttfb_ms INTEGER NULL,
+    FOREIGN KEY (status_id) REFERENCES status_entry(id)
+);
+
+INSERT INTO status_history_new (id, status_id, status_code, created, latency_ms, ttfb_ms)
+SELECT id, status_id, coalesce(status_code, -1), created, latency_ms, ttfb_ms
+FROM status_history;
+
+DROP TABLE status_history;
+
+ALTER TABLE status_history_new RENAME TO status_history;
+
+CREATE INDEX status_history_status_id_idx ON status_history (status_id);
+CREATE INDEX idx_status_history_status_id ON status_history(status_id, created DESC);
@@ -0,0 +1,31 @@
+-- make status_code nullable and record why a check failed instead of storing -1
+CREATE TABLE status_history_new (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    status_id INTEGER NOT NULL,
+    -- NULL if no response was received
+    status_code INTEGER NULL,
+    created DATETIME DEFAULT CURRENT_TIMESTAMP,
+    latency_ms INTEGER NULL,
+    ttfb_ms INTEGER NULL,
+    error_kind VARCHAR NULL,
+    error_message VARCHAR NULL,
+    FOREIGN KEY (status_id) REFERENCES status_entry(id)
+);
+
+INSERT INTO status_history_new (id, status_id, status_code, created, latency_ms, ttfb_ms, error_kind)
+SELECT
+    id,
+    status_id,
+    CASE WHEN status_code = -1 THEN NULL ELSE status_code END,
+    created,
+    latency_ms,
+    ttfb_ms,
+    CASE WHEN status_code = -1 THEN 'unknown' ELSE NULL END
+FROM status_history;
+
+DROP TABLE status_history;
+
+ALTER TABLE status_history_new RENAME TO status_history;
+
+CREATE INDEX status_history_status_id_idx ON status_history (status_id);
+CREATE INDEX idx_status_history_status_id ON status_history(status_id, created DESC);
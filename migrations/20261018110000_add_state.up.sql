@@ -0,0 +1,9 @@
+-- verdict computed by the poller from the entry's accepted status codes
+ALTER TABLE status_history ADD COLUMN state VARCHAR NOT NULL DEFAULT 'down';
+
+UPDATE status_history
+SET state = CASE
+    WHEN status_code BETWEEN 200 AND 299 AND error_kind IS NULL THEN 'up'
+    WHEN status_code BETWEEN 300 AND 399 AND error_kind IS NULL THEN 'degraded'
+    ELSE 'down'
+END;
@@ -0,0 +1,9 @@
+CREATE TABLE status_assertion_failure (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    history_id INTEGER NOT NULL,
+    assertion VARCHAR NOT NULL,
+    message VARCHAR NOT NULL,
+    FOREIGN KEY (history_id) REFERENCES status_history(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_status_assertion_failure_history_id ON status_assertion_failure (history_id);
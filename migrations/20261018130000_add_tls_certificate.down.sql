@@ -0,0 +1 @@
+DROP TABLE tls_certificate;
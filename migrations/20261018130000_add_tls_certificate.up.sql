@@ -0,0 +1,12 @@
+-- latest peer certificate seen by the checks of an entry
+CREATE TABLE tls_certificate (
+    status_id INTEGER PRIMARY KEY NOT NULL,
+    subject VARCHAR NOT NULL,
+    issuer VARCHAR NOT NULL,
+    -- comma separated subject alternative names
+    sans VARCHAR NOT NULL,
+    not_before DATETIME NOT NULL,
+    not_after DATETIME NOT NULL,
+    checked DATETIME DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (status_id) REFERENCES status_entry(id) ON DELETE CASCADE
+);
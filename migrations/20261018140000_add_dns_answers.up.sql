@@ -0,0 +1,2 @@
+-- comma separated answer set of dns checks
+ALTER TABLE status_history ADD COLUMN answers VARCHAR NULL;
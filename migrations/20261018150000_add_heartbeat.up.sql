@@ -0,0 +1,11 @@
+-- push monitors, the token is the secret part of the ping url
+CREATE TABLE heartbeat (
+    status_id INTEGER PRIMARY KEY NOT NULL,
+    token VARCHAR NOT NULL UNIQUE DEFAULT (lower(hex(randomblob(16)))),
+    created DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    last_ping DATETIME NULL,
+    -- state and message reported by the last ping
+    state VARCHAR NULL,
+    message VARCHAR NULL,
+    FOREIGN KEY (status_id) REFERENCES status_entry(id) ON DELETE CASCADE
+);
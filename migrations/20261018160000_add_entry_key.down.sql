@@ -0,0 +1,4 @@
+ALTER TABLE status_entry DROP COLUMN archived;
+DROP INDEX entry_key_unique;
+ALTER TABLE status_entry DROP COLUMN key;
+CREATE UNIQUE INDEX entry_name_unique ON status_entry (name);
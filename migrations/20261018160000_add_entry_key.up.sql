@@ -0,0 +1,8 @@
+-- entries are matched by a stable key instead of their name, and archived instead of deleted
+ALTER TABLE status_entry ADD COLUMN key VARCHAR NOT NULL DEFAULT '';
+UPDATE status_entry SET key = name;
+DROP INDEX entry_name_unique;
+CREATE UNIQUE INDEX entry_key_unique ON status_entry (key);
+
+-- NULL for entries in the config
+ALTER TABLE status_entry ADD COLUMN archived DATETIME NULL;
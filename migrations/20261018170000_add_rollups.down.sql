@@ -0,0 +1,2 @@
+DROP TABLE rollup_state;
+DROP TABLE status_rollup;
@@ -0,0 +1,25 @@
+-- checks aggregated per entry and hour or day, kept after the raw checks are pruned
+CREATE TABLE status_rollup (
+    status_id INTEGER NOT NULL,
+    -- 'hour' or 'day'
+    period VARCHAR NOT NULL,
+    -- start of the bucket
+    bucket DATETIME NOT NULL,
+    checks INTEGER NOT NULL,
+    failures INTEGER NOT NULL,
+    degraded INTEGER NOT NULL,
+    latency_min_ms INTEGER NULL,
+    latency_avg_ms REAL NULL,
+    latency_max_ms INTEGER NULL,
+    latency_p95_ms INTEGER NULL,
+    PRIMARY KEY (status_id, period, bucket),
+    FOREIGN KEY (status_id) REFERENCES status_entry(id) ON DELETE CASCADE
+);
+
+-- status_history rows up to this id are included in the rollups
+CREATE TABLE rollup_state (
+    id INTEGER PRIMARY KEY NOT NULL CHECK (id = 1),
+    last_history_id INTEGER NOT NULL
+);
+
+INSERT INTO rollup_state (id, last_history_id) VALUES (1, 0);
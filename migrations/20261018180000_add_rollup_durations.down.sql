@@ -0,0 +1,3 @@
+ALTER TABLE status_rollup DROP COLUMN down_ms;
+ALTER TABLE status_rollup DROP COLUMN degraded_ms;
+ALTER TABLE status_rollup DROP COLUMN up_ms;
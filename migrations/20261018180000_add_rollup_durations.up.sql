@@ -0,0 +1,7 @@
+-- time in each state, every check lasts until the next one
+ALTER TABLE status_rollup ADD COLUMN up_ms INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE status_rollup ADD COLUMN degraded_ms INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE status_rollup ADD COLUMN down_ms INTEGER NOT NULL DEFAULT 0;
+
+-- recompute the existing buckets from the checks that are still stored
+UPDATE rollup_state SET last_history_id = 0;
@@ -0,0 +1,16 @@
+-- error budget of the entries with an slo, updated in the background
+CREATE TABLE slo_status (
+    status_id INTEGER PRIMARY KEY NOT NULL,
+    -- percentage of the time the entry must be up
+    target REAL NOT NULL,
+    window_secs INTEGER NOT NULL,
+    -- downtime within the window
+    down_ms INTEGER NOT NULL,
+    -- fraction of the error budget left, negative once it is exceeded
+    budget_remaining REAL NOT NULL,
+    burn_rate_1h REAL NOT NULL,
+    burn_rate_6h REAL NOT NULL,
+    burning_fast BOOLEAN NOT NULL,
+    updated DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (status_id) REFERENCES status_entry(id) ON DELETE CASCADE
+);
@@ -0,0 +1,17 @@
+-- outages derived from the state changes of the checks
+CREATE TABLE incident (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    status_id INTEGER NOT NULL,
+    -- time of the first failed check
+    started DATETIME NOT NULL,
+    -- time of the first check that was up again, NULL while ongoing
+    ended DATETIME NULL,
+    -- failure of the first failed check
+    error_kind VARCHAR NULL,
+    error_message VARCHAR NULL,
+    FOREIGN KEY (status_id) REFERENCES status_entry(id) ON DELETE CASCADE
+);
+
+CREATE INDEX incident_status_id_idx ON incident (status_id, started DESC);
+-- at most one ongoing incident per entry
+CREATE UNIQUE INDEX incident_ongoing_unique ON incident (status_id) WHERE ended IS NULL;
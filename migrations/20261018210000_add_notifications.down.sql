@@ -0,0 +1,2 @@
+DROP TABLE notification_delivery;
+DROP TABLE state_change;
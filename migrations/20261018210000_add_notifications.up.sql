@@ -0,0 +1,39 @@
+-- state changes of the entries, written with the check that caused them
+CREATE TABLE state_change (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    status_id INTEGER NOT NULL,
+    -- NULL for the first check of an entry
+    old_state VARCHAR NULL,
+    new_state VARCHAR NOT NULL,
+    error_kind VARCHAR NULL,
+    error_message VARCHAR NULL,
+    -- time of the check that changed the state
+    created DATETIME NOT NULL,
+    -- time of the last check in the old state
+    previous_check DATETIME NULL,
+    -- set once a delivery was queued for every notifier
+    dispatched BOOLEAN NOT NULL DEFAULT FALSE,
+    FOREIGN KEY (status_id) REFERENCES status_entry(id) ON DELETE CASCADE
+);
+
+CREATE INDEX state_change_pending_idx ON state_change (id) WHERE NOT dispatched;
+
+-- delivery log of the notifications, one row per state change and notifier
+CREATE TABLE notification_delivery (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    state_change_id INTEGER NOT NULL,
+    notifier VARCHAR NOT NULL,
+    created DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    next_attempt DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    delivered DATETIME NULL,
+    -- set when the delivery is given up
+    failed DATETIME NULL,
+    last_error VARCHAR NULL,
+    FOREIGN KEY (state_change_id) REFERENCES state_change(id) ON DELETE CASCADE
+);
+
+CREATE INDEX notification_delivery_pending_idx ON notification_delivery (next_attempt)
+WHERE
+    delivered IS NULL
+    AND failed IS NULL;
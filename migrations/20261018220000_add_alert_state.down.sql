@@ -0,0 +1,2 @@
+DROP TABLE alert_state;
+ALTER TABLE state_change DROP COLUMN kind;
@@ -0,0 +1,23 @@
+-- 'change', 'reminder', 'flapping' or 'flapping_stopped'
+ALTER TABLE state_change ADD COLUMN kind VARCHAR NOT NULL DEFAULT 'change';
+
+-- what the notifiers were last told about each entry
+CREATE TABLE alert_state (
+    status_id INTEGER PRIMARY KEY NOT NULL,
+    notified_state VARCHAR NULL,
+    flapping BOOLEAN NOT NULL DEFAULT FALSE,
+    last_notified DATETIME NULL,
+    FOREIGN KEY (status_id) REFERENCES status_entry(id) ON DELETE CASCADE
+);
+
+-- the last state change of each entry was notified already
+INSERT INTO
+    alert_state (status_id, notified_state, last_notified)
+SELECT
+    status_id,
+    new_state,
+    MAX(created)
+FROM
+    state_change
+GROUP BY
+    status_id;
@@ -0,0 +1,28 @@
+-- checks before this time may be pruned, their buckets are not recomputed
+ALTER TABLE rollup_state ADD COLUMN pruned_until DATETIME NULL;
+
+-- rollups before the oldest stored check mean that checks were pruned already
+UPDATE
+    rollup_state
+SET
+    pruned_until = (
+        SELECT
+            MIN(created)
+        FROM
+            status_history
+    )
+WHERE
+    EXISTS (
+        SELECT
+            1
+        FROM
+            status_rollup
+        WHERE
+            period = 'hour'
+            AND bucket < (
+                SELECT
+                    strftime('%Y-%m-%d %H:00:00', MIN(created))
+                FROM
+                    status_history
+            )
+    );
@@ -6,16 +6,266 @@ use leptos_router::hooks::{use_location, use_params};
 use leptos_router::params::Params;
 use leptos_router::path;
 use serde_derive::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::time::Duration;
 
-#[derive(Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct Entry {
+    /// Stable identifier of the entry, defaults to `name`
+    ///
+    /// Set it to the old name when renaming an entry to keep its history
+    #[serde(default)]
+    pub key: Option<String>,
     pub name: String,
     pub public_url: url::Url,
     pub polling_url: Option<url::Url>,
+    /// Overrides the global `poll_interval` for this entry
+    #[cfg_attr(
+        feature = "ssr",
+        serde(deserialize_with = "de_duration::deser_duration")
+    )]
+    #[serde(default)]
+    pub interval: Option<Duration>,
+    /// Overrides the global `timeout` for this entry
+    #[cfg_attr(
+        feature = "ssr",
+        serde(deserialize_with = "de_duration::deser_duration")
+    )]
+    #[serde(default)]
+    pub timeout: Option<Duration>,
+    /// Overrides the global `retries` for this entry
+    #[serde(default)]
+    pub retries: Option<u32>,
+    /// Overrides the global `cert_expiry_days` for this entry
+    #[serde(default)]
+    pub cert_expiry_days: Option<u32>,
+    /// HTTP method of the check, defaults to GET
+    #[serde(default)]
+    pub method: Option<String>,
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    #[serde(default)]
+    pub user_agent: Option<String>,
+    /// Overrides the `Host` header, e.g. to check a virtual host through `polling_url`
+    #[serde(default)]
+    pub host: Option<String>,
+    /// Request body sent as is
+    #[serde(default)]
+    pub body: Option<String>,
+    /// Request body sent as JSON
+    #[serde(default)]
+    pub json_body: Option<serde_json::Value>,
+    /// Status codes that count as up, defaults to 2xx
+    #[serde(default)]
+    pub accepted_status: Option<Vec<StatusRange>>,
+    /// Status codes that count as degraded, defaults to 3xx
+    #[serde(default)]
+    pub degraded_status: Option<Vec<StatusRange>>,
+    /// Strings that must appear in the response body
+    #[serde(default)]
+    pub body_contains: Vec<String>,
+    /// Strings that must not appear in the response body
+    #[serde(default)]
+    pub body_not_contains: Vec<String>,
+    /// Regex the response body must match
+    #[serde(default)]
+    pub body_matches: Option<String>,
+    /// Assertions on a JSON response body
+    #[serde(default)]
+    pub json_assertions: Vec<JsonAssertion>,
+    /// Maximum number of response body bytes read for the assertions
+    #[serde(default)]
+    pub max_body_bytes: Option<usize>,
+    /// Answers that must all be present in the answer set of a `dns://` monitor
+    #[serde(default)]
+    pub dns_answers: Vec<String>,
+    /// Expected time between the pings of a `heartbeat:` monitor
+    #[cfg_attr(
+        feature = "ssr",
+        serde(deserialize_with = "de_duration::deser_duration")
+    )]
+    #[serde(default)]
+    pub heartbeat_period: Option<Duration>,
+    /// How late a ping may be before the heartbeat monitor is down, defaults to 1 minute
+    #[cfg_attr(
+        feature = "ssr",
+        serde(deserialize_with = "de_duration::deser_duration")
+    )]
+    #[serde(default)]
+    pub heartbeat_grace: Option<Duration>,
+    /// Availability objective, e.g. `slo = { target = 99.9, window = "30d" }`
+    #[serde(default)]
+    pub slo: Option<Slo>,
+    /// Group in `groups` whose notifiers get the state changes of the entry
+    #[serde(default)]
+    pub group: Option<String>,
+    /// Names of the notifiers that get the state changes, overrides the group
+    #[serde(default)]
+    pub notify: Option<Vec<String>>,
+    /// Overrides the fields of the global `notify_policy` for this entry
+    #[serde(default)]
+    pub notify_policy: Option<NotifyPolicy>,
+}
+
+/// When the state changes of an entry are notified, e.g. `{ alert_after = 3, remind_every = "1h" }`
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NotifyPolicy {
+    /// Consecutive failed checks before an entry is notified as down, defaults to 1
+    #[serde(default)]
+    pub alert_after: Option<u32>,
+    /// Consecutive successful checks before a recovery is notified, defaults to 1
+    #[serde(default)]
+    pub recover_after: Option<u32>,
+    /// Repeats the down notification while the entry stays down
+    #[cfg_attr(
+        feature = "ssr",
+        serde(deserialize_with = "de_duration::deser_duration")
+    )]
+    #[serde(default)]
+    pub remind_every: Option<Duration>,
+    /// State changes within `flap_window` checks that make an entry flapping, off if unset
+    #[serde(default)]
+    pub flap_threshold: Option<u32>,
+    /// Number of recent checks looked at for flapping, defaults to 20
+    #[serde(default)]
+    pub flap_window: Option<u32>,
+}
+
+/// Service level objective of an entry, degraded checks count as up
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Slo {
+    /// Percentage of the time the entry must be up
+    pub target: f64,
+    /// Rolling window of the objective, defaults to 30 days
+    #[cfg_attr(
+        feature = "ssr",
+        serde(deserialize_with = "de_duration::deser_duration")
+    )]
+    #[serde(default)]
+    pub window: Option<Duration>,
+}
+
+impl Slo {
+    pub const DEFAULT_WINDOW: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+    pub fn window(&self) -> Duration {
+        self.window.unwrap_or(Self::DEFAULT_WINDOW)
+    }
+}
+
+/// Assertion on the value at a JSON pointer, e.g. `{ pointer = "/db", equals = "up" }`
+#[derive(Debug, Clone, Deserialize)]
+pub struct JsonAssertion {
+    pub pointer: String,
+    #[serde(flatten)]
+    pub check: JsonCheck,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JsonCheck {
+    Equals(serde_json::Value),
+    Exists(bool),
+    GreaterThan(f64),
+    LessThan(f64),
+}
+
+impl std::fmt::Display for JsonAssertion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let pointer = &self.pointer;
+        match &self.check {
+            JsonCheck::Equals(value) => write!(f, "{pointer} equals {value}"),
+            JsonCheck::Exists(true) => write!(f, "{pointer} exists"),
+            JsonCheck::Exists(false) => write!(f, "{pointer} does not exist"),
+            JsonCheck::GreaterThan(n) => write!(f, "{pointer} > {n}"),
+            JsonCheck::LessThan(n) => write!(f, "{pointer} < {n}"),
+        }
+    }
+}
+
+impl Entry {
+    pub fn key(&self) -> &str {
+        self.key.as_deref().unwrap_or(&self.name)
+    }
+
+    pub fn status_state(&self, status: u16) -> CheckState {
+        const ACCEPTED: &[StatusRange] = &[StatusRange::new(200, 299)];
+        const DEGRADED: &[StatusRange] = &[StatusRange::new(300, 399)];
+
+        let matches = |ranges: &Option<Vec<StatusRange>>, default: &[StatusRange]| {
+            ranges
+                .as_deref()
+                .unwrap_or(default)
+                .iter()
+                .any(|r| r.contains(status))
+        };
+        if matches(&self.accepted_status, ACCEPTED) {
+            CheckState::Up
+        } else if matches(&self.degraded_status, DEGRADED) {
+            CheckState::Degraded
+        } else {
+            CheckState::Down
+        }
+    }
+}
+
+/// Inclusive range of HTTP status codes, written as `401`, `"2xx"` or `"200-204"` in the config
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "StatusRangeRepr")]
+pub struct StatusRange {
+    pub start: u16,
+    pub end: u16,
+}
+
+impl StatusRange {
+    pub const fn new(start: u16, end: u16) -> Self {
+        Self { start, end }
+    }
+
+    pub fn contains(&self, status: u16) -> bool {
+        self.start <= status && status <= self.end
+    }
 }
 
 #[derive(Deserialize)]
+#[serde(untagged)]
+enum StatusRangeRepr {
+    Code(u16),
+    Text(String),
+}
+
+impl TryFrom<StatusRangeRepr> for StatusRange {
+    type Error = String;
+
+    fn try_from(value: StatusRangeRepr) -> Result<Self, Self::Error> {
+        let text = match value {
+            StatusRangeRepr::Code(code) => return Ok(Self::new(code, code)),
+            StatusRangeRepr::Text(text) => text,
+        };
+        let parse = |s: &str| {
+            s.trim()
+                .parse::<u16>()
+                .map_err(|_| format!("Invalid status code range: {text:?}"))
+        };
+        if let Some(class) = text.strip_suffix("xx") {
+            let class = parse(class)?;
+            if class > 9 {
+                return Err(format!("Invalid status code class: {text:?}"));
+            }
+            return Ok(Self::new(class * 100, class * 100 + 99));
+        }
+        match text.split_once('-') {
+            Some((start, end)) => Ok(Self::new(parse(start)?, parse(end)?)),
+            None => parse(&text).map(|code| Self::new(code, code)),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct Config {
     #[cfg_attr(
         feature = "ssr",
@@ -23,9 +273,126 @@ pub struct Config {
     )]
     #[serde(default)]
     pub poll_interval: Option<Duration>,
+    /// How long a check may take before it fails
+    #[cfg_attr(
+        feature = "ssr",
+        serde(deserialize_with = "de_duration::deser_duration")
+    )]
+    #[serde(default)]
+    pub timeout: Option<Duration>,
+    /// Number of times a failed check is retried before the entry is marked down
+    #[serde(default)]
+    pub retries: Option<u32>,
+    /// Delay between retries of a failed check
+    #[cfg_attr(
+        feature = "ssr",
+        serde(deserialize_with = "de_duration::deser_duration")
+    )]
+    #[serde(default)]
+    pub retry_delay: Option<Duration>,
+    /// Entries are degraded once their TLS certificate expires within this many days
+    #[serde(default)]
+    pub cert_expiry_days: Option<u32>,
+    /// Maximum number of checks running at the same time, only read at startup
+    #[serde(default)]
+    pub max_concurrency: Option<usize>,
+    /// Maximum number of checks running at the same time against a single host, only read at
+    /// startup
+    #[serde(default)]
+    pub max_concurrency_per_host: Option<usize>,
+    /// How long check results, notifications and ended incidents are kept, forever if unset,
+    /// only read at startup
+    #[cfg_attr(
+        feature = "ssr",
+        serde(deserialize_with = "de_duration::deser_duration")
+    )]
+    #[serde(default)]
+    pub retention: Option<Duration>,
+    /// Public url of this dashboard, used for links in notifications, only read at startup
+    #[serde(default)]
+    pub dashboard_url: Option<url::Url>,
+    /// Where the state changes of the entries are sent, all of them unless routed otherwise
+    #[serde(default)]
+    pub notifiers: Vec<NotifierConfig>,
+    /// Notification routing of the entries by group, e.g. `[groups.payments]`
+    #[serde(default)]
+    pub groups: BTreeMap<String, Group>,
+    /// Default notification policy of the entries
+    #[serde(default)]
+    pub notify_policy: NotifyPolicy,
     pub entries: Vec<Entry>,
 }
 
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Group {
+    /// Names of the notifiers that get the state changes of the entries in the group
+    pub notify: Vec<String>,
+}
+
+/// Notifier in the config, e.g. `{ name = "ops", type = "webhook", url = "https://..." }`
+#[derive(Debug, Clone, Deserialize)]
+pub struct NotifierConfig {
+    /// Name of the notifier in the delivery log
+    pub name: String,
+    #[serde(flatten)]
+    pub kind: NotifierKind,
+}
+
+/// Unknown fields are rejected here, `NotifierConfig` can not as it flattens this
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum NotifierKind {
+    /// POSTs every state change as JSON
+    Webhook {
+        url: url::Url,
+        #[serde(default)]
+        headers: BTreeMap<String, String>,
+    },
+    /// Sends an email when an entry goes down and when it recovers
+    Smtp {
+        host: String,
+        /// Defaults to 587 with STARTTLS, 465 with implicit TLS and 25 without TLS
+        #[serde(default)]
+        port: Option<u16>,
+        #[serde(default)]
+        tls: SmtpTls,
+        #[serde(default)]
+        username: Option<String>,
+        /// Environment variable holding the password
+        #[serde(default)]
+        password_env: Option<String>,
+        /// File holding the password, e.g. a mounted secret
+        #[serde(default)]
+        password_file: Option<std::path::PathBuf>,
+        from: String,
+        to: Vec<String>,
+    },
+    /// Posts to a Slack incoming webhook
+    Slack { url: url::Url },
+    /// Posts to a Discord webhook
+    Discord { url: url::Url },
+    /// Posts to a Mattermost incoming webhook
+    Mattermost {
+        url: url::Url,
+        /// Overrides the channel of the webhook
+        #[serde(default)]
+        channel: Option<String>,
+    },
+    /// Posts an adaptive card to a Microsoft Teams workflow webhook
+    Teams { url: url::Url },
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SmtpTls {
+    #[default]
+    Starttls,
+    Implicit,
+    /// Plain text, only for local relays and test sinks
+    None,
+}
+
 #[cfg(feature = "ssr")]
 mod de_duration {
     use super::Duration;
@@ -59,14 +426,99 @@ pub mod ssr {
     }
 }
 
+/// Outcome of a single check
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "ssr", derive(sqlx::Type))]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "ssr", sqlx(rename_all = "snake_case"))]
+pub enum CheckState {
+    Up,
+    Degraded,
+    Down,
+}
+
+impl std::fmt::Display for CheckState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            CheckState::Up => "Up",
+            CheckState::Degraded => "Degraded",
+            CheckState::Down => "Down",
+        };
+        f.write_str(s)
+    }
+}
+
+/// Length of the buckets of the check rollups
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "ssr", derive(sqlx::Type))]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "ssr", sqlx(rename_all = "snake_case"))]
+pub enum RollupPeriod {
+    Hour,
+    Day,
+}
+
+/// Why a check failed
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "ssr", derive(sqlx::Type))]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "ssr", sqlx(rename_all = "snake_case"))]
+pub enum ErrorKind {
+    Dns,
+    ConnectionRefused,
+    Connect,
+    Timeout,
+    Tls,
+    TooManyRedirects,
+    Body,
+    Assertion,
+    CertificateExpiring,
+    MissedHeartbeat,
+    JobFailed,
+    Unknown,
+}
+
+impl std::fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            ErrorKind::Dns => "DNS failure",
+            ErrorKind::ConnectionRefused => "Connection refused",
+            ErrorKind::Connect => "Connection failed",
+            ErrorKind::Timeout => "Timeout",
+            ErrorKind::Tls => "TLS error",
+            ErrorKind::TooManyRedirects => "Too many redirects",
+            ErrorKind::Body => "Failed to read response body",
+            ErrorKind::Assertion => "Assertion failed",
+            ErrorKind::CertificateExpiring => "Certificate expires soon",
+            ErrorKind::MissedHeartbeat => "Missed heartbeat",
+            ErrorKind::JobFailed => "Job reported a failure",
+            ErrorKind::Unknown => "Unknown error",
+        };
+        f.write_str(s)
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[cfg_attr(feature = "ssr", derive(sqlx::FromRow))]
 pub struct StatusRow {
     pub id: i64,
     pub public_url: String,
     pub name: String,
-    pub last_status: i64,
+    pub last_status: Option<i64>,
+    pub state: CheckState,
     pub poll_time: chrono::NaiveDateTime,
+    pub latency_ms: Option<i64>,
+    pub ttfb_ms: Option<i64>,
+    pub error_kind: Option<ErrorKind>,
+    pub error_message: Option<String>,
+    /// Percentage of the time up or degraded, None without any checked time
+    pub uptime_24h: Option<f64>,
+    pub uptime_7d: Option<f64>,
+    pub uptime_30d: Option<f64>,
+    pub uptime_90d: Option<f64>,
+    /// Fraction of the error budget left, for entries with an slo
+    pub budget_remaining: Option<f64>,
+    pub burning_fast: Option<bool>,
 }
 
 #[server(GetSatuses, "/status")]
@@ -76,13 +528,102 @@ async fn list_statuses() -> Result<Vec<StatusRow>, ServerFnError> {
     sqlx::query_as!(
         StatusRow,
         r#"
-WITH ranked_history AS (
+WITH windows (name, period, since, first_bucket, bucket_days) AS (
+    -- short windows from the hourly rollups, long windows from the daily ones
+    VALUES
+        (
+            '24h',
+            'hour',
+            datetime('now', '-1 day'),
+            strftime('%Y-%m-%d %H:00:00', 'now', '-1 day'),
+            1.0 / 24
+        ),
+        (
+            '7d',
+            'hour',
+            datetime('now', '-7 days'),
+            strftime('%Y-%m-%d %H:00:00', 'now', '-7 days'),
+            1.0 / 24
+        ),
+        (
+            '30d',
+            'day',
+            datetime('now', '-30 days'),
+            date('now', '-30 days'),
+            1.0
+        ),
+        (
+            '90d',
+            'day',
+            datetime('now', '-90 days'),
+            date('now', '-90 days'),
+            1.0
+        )
+),
+weighted AS (
+    SELECT
+        r.status_id,
+        w.name,
+        -- only the part of the first bucket within the window counts
+        MIN(
+            1.0,
+            (julianday(r.bucket) + w.bucket_days - julianday(w.since)) / w.bucket_days
+        ) AS weight,
+        r.up_ms + r.degraded_ms AS up_ms,
+        r.up_ms + r.degraded_ms + r.down_ms AS total_ms
+    FROM
+        status_rollup AS r
+        INNER JOIN windows AS w ON w.period = r.period
+        AND r.bucket >= w.first_bucket
+),
+uptime AS (
+    SELECT
+        status_id,
+        100.0 * SUM(weight * up_ms) FILTER (
+            WHERE
+                name = '24h'
+        ) / SUM(weight * total_ms) FILTER (
+            WHERE
+                name = '24h'
+        ) AS uptime_24h,
+        100.0 * SUM(weight * up_ms) FILTER (
+            WHERE
+                name = '7d'
+        ) / SUM(weight * total_ms) FILTER (
+            WHERE
+                name = '7d'
+        ) AS uptime_7d,
+        100.0 * SUM(weight * up_ms) FILTER (
+            WHERE
+                name = '30d'
+        ) / SUM(weight * total_ms) FILTER (
+            WHERE
+                name = '30d'
+        ) AS uptime_30d,
+        100.0 * SUM(weight * up_ms) FILTER (
+            WHERE
+                name = '90d'
+        ) / SUM(weight * total_ms) FILTER (
+            WHERE
+                name = '90d'
+        ) AS uptime_90d
+    FROM
+        weighted
+    GROUP BY
+        status_id
+),
+ranked_history AS (
     SELECT
         se.id,
         public_url AS "public_url!",
         se."name" AS "name!",
-        status_code AS "last_status!",
+        status_code AS last_status,
+        sh.state,
         sh."created" AS "poll_time!",
+        sh.latency_ms,
+        sh.ttfb_ms,
+        sh.error_kind,
+        sh.error_message,
         row_number() over (
             PARTITION by se.id
             ORDER BY
@@ -94,19 +635,40 @@ WITH ranked_history AS (
             SELECT
                 status_id,
                 status_code,
-                created
+                state,
+                created,
+                latency_ms,
+                ttfb_ms,
+                error_kind,
+                error_message
             FROM
                 status_history
         ) AS sh ON sh.status_id = se.id
+    WHERE
+        se.archived IS NULL
 )
 SELECT
     id,
     "public_url!",
     "name!",
-    "last_status!",
-    "poll_time!"
+    last_status AS "last_status?",
+    state AS "state!: CheckState",
+    "poll_time!",
+    latency_ms AS "latency_ms?",
+    ttfb_ms AS "ttfb_ms?",
+    error_kind AS "error_kind?: ErrorKind",
+    error_message AS "error_message?",
+    -- NULL when there is no checked time, the division by 0 is NULL too
+    uptime_24h AS "uptime_24h?: f64",
+    uptime_7d AS "uptime_7d?: f64",
+    uptime_30d AS "uptime_30d?: f64",
+    uptime_90d AS "uptime_90d?: f64",
+    budget_remaining AS "budget_remaining?",
+    burning_fast AS "burning_fast?"
 FROM
     ranked_history
+    LEFT JOIN uptime ON uptime.status_id = ranked_history.id
+    LEFT JOIN slo_status ON slo_status.status_id = ranked_history.id
 WHERE
     rn <= 10
 ORDER BY
@@ -122,20 +684,240 @@ ORDER BY
     })
 }
 
+/// Entry removed from the config, kept with its history
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "ssr", derive(sqlx::FromRow))]
+pub struct ArchivedRow {
+    pub id: i64,
+    pub key: String,
+    pub name: String,
+    pub public_url: String,
+    pub archived: chrono::NaiveDateTime,
+}
+
+#[server(GetArchived, "/archived")]
+async fn list_archived() -> Result<Vec<ArchivedRow>, ServerFnError> {
+    let state = expect_context::<ssr::AppState>();
+    let db = &state.db;
+    sqlx::query_as!(
+        ArchivedRow,
+        r#"
+SELECT
+    id,
+    key,
+    name,
+    public_url,
+    archived AS "archived!"
+FROM
+    status_entry
+WHERE
+    archived IS NOT NULL
+ORDER BY
+    archived DESC
+"#
+    )
+    .fetch_all(db)
+    .await
+    .map_err(|err| {
+        leptos::logging::error!("Failed to load archived entries: {err:?}");
+        ServerFnError::ServerError("Failed to load archived entries".to_owned())
+    })
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[cfg_attr(feature = "ssr", derive(sqlx::FromRow))]
 pub struct StatusDetails {
     pub id: i64,
     pub public_url: String,
     pub name: String,
+    pub archived: Option<chrono::NaiveDateTime>,
+    /// Checks of the last day, longer views are read from the rollups
     pub history: Vec<HistoryRow>,
+    /// Assertions that failed in the latest check
+    pub failed_assertions: Vec<FailedAssertion>,
+    pub certificate: Option<CertificateRow>,
+    pub heartbeat: Option<HeartbeatRow>,
+    pub slo: Option<SloRow>,
+    /// Incidents of the last 90 days and the ongoing one, newest first
+    pub incidents: Vec<IncidentRow>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "ssr", derive(sqlx::FromRow))]
+pub struct IncidentRow {
+    pub started: chrono::NaiveDateTime,
+    pub ended: Option<chrono::NaiveDateTime>,
+    pub error_kind: Option<ErrorKind>,
+    pub error_message: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IncidentStats {
+    /// Mean time to recovery of the resolved incidents
+    pub mttr: Option<chrono::TimeDelta>,
+    /// Mean time between the end of an incident and the start of the next one
+    pub mtbf: Option<chrono::TimeDelta>,
+}
+
+impl IncidentStats {
+    /// `incidents` are sorted newest first
+    pub fn new(incidents: &[IncidentRow]) -> Self {
+        let mean = |deltas: Vec<chrono::TimeDelta>| {
+            let count = i32::try_from(deltas.len()).ok().filter(|&c| c > 0)?;
+            Some(deltas.into_iter().sum::<chrono::TimeDelta>() / count)
+        };
+        Self {
+            mttr: mean(
+                incidents
+                    .iter()
+                    .filter_map(|i| Some(i.ended? - i.started))
+                    .collect(),
+            ),
+            mtbf: mean(
+                incidents
+                    .windows(2)
+                    .filter_map(|w| Some(w[0].started - w[1].ended?))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+/// Error budget of an entry with an slo
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "ssr", derive(sqlx::FromRow))]
+pub struct SloRow {
+    pub target: f64,
+    pub window_secs: i64,
+    pub down_ms: i64,
+    /// Fraction of the budget left, negative once it is exceeded
+    pub budget_remaining: f64,
+    /// How fast the budget is spent, 1 spends exactly the budget over the window
+    pub burn_rate_1h: f64,
+    pub burn_rate_6h: f64,
+    pub burning_fast: bool,
+    pub updated: chrono::NaiveDateTime,
+}
+
+impl SloRow {
+    /// Allowed downtime over the window
+    pub fn budget_ms(&self) -> f64 {
+        (1.0 - self.target / 100.0) * self.window_secs as f64 * 1000.0
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "ssr", derive(sqlx::FromRow))]
+pub struct CertificateRow {
+    pub subject: String,
+    pub issuer: String,
+    pub sans: String,
+    pub not_before: chrono::NaiveDateTime,
+    pub not_after: chrono::NaiveDateTime,
+}
+
+/// Checks of an entry aggregated over an hour or a day
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "ssr", derive(sqlx::FromRow))]
+pub struct RollupRow {
+    pub bucket: chrono::NaiveDateTime,
+    pub checks: i64,
+    pub failures: i64,
+    pub degraded: i64,
+    pub latency_min_ms: Option<i64>,
+    pub latency_avg_ms: Option<f64>,
+    pub latency_max_ms: Option<i64>,
+    pub latency_p95_ms: Option<i64>,
+}
+
+/// Rollups of the entry for the last `days` days, oldest first
+#[server(GetRollups, "/status")]
+async fn get_rollups(
+    id: i64,
+    period: RollupPeriod,
+    days: u32,
+) -> Result<Vec<RollupRow>, ServerFnError> {
+    /// Rollups are never pruned, but nobody looks further back than this
+    const MAX_DAYS: u32 = 10 * 366;
+
+    let state = expect_context::<ssr::AppState>();
+    let db = &state.db;
+    let since = chrono::Utc::now()
+        .naive_utc()
+        .checked_sub_signed(chrono::TimeDelta::days(days.min(MAX_DAYS).into()))
+        .ok_or_else(|| <ServerFnError>::ServerError("Invalid number of days".to_owned()))?
+        .format("%Y-%m-%d 00:00:00")
+        .to_string();
+    sqlx::query_as!(
+        RollupRow,
+        r#"
+SELECT
+    bucket,
+    checks,
+    failures,
+    degraded,
+    latency_min_ms,
+    latency_avg_ms,
+    latency_max_ms,
+    latency_p95_ms
+FROM
+    status_rollup
+WHERE
+    status_id = ?
+    AND period = ?
+    AND bucket >= ?
+ORDER BY
+    bucket
+"#,
+        id,
+        period,
+        since
+    )
+    .fetch_all(db)
+    .await
+    .map_err(|err| {
+        leptos::logging::error!("Failed to load rollups: {err:?}");
+        ServerFnError::ServerError("Failed to load rollups".to_owned())
+    })
+}
+
+/// Last ping received by a heartbeat monitor
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "ssr", derive(sqlx::FromRow))]
+pub struct HeartbeatRow {
+    pub last_ping: Option<chrono::NaiveDateTime>,
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "ssr", derive(sqlx::FromRow))]
+pub struct FailedAssertion {
+    pub assertion: String,
+    pub message: String,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[cfg_attr(feature = "ssr", derive(sqlx::FromRow))]
 pub struct HistoryRow {
-    pub status: i64,
+    pub status: Option<i64>,
+    pub state: CheckState,
     pub poll_time: chrono::NaiveDateTime,
+    pub latency_ms: Option<i64>,
+    pub ttfb_ms: Option<i64>,
+    pub error_kind: Option<ErrorKind>,
+    pub error_message: Option<String>,
+    /// Comma separated answer set of dns checks
+    pub answers: Option<String>,
+}
+
+impl HistoryRow {
+    fn error_summary(&self) -> Option<String> {
+        let kind = self.error_kind?;
+        Some(match self.error_message.as_ref() {
+            Some(msg) => format!("{kind}: {msg}"),
+            None => kind.to_string(),
+        })
+    }
 }
 
 #[server(GetSatus, "/status/{id}")]
@@ -143,11 +925,13 @@ async fn get_status_details(id: i64) -> Result<StatusDetails, ServerFnError> {
     let state = expect_context::<ssr::AppState>();
     let db = &state.db;
 
+    #[allow(deprecated)]
     let header = sqlx::query!(
         r#"
 SELECT
     name AS "name!",
-    public_url AS "public_url!"
+    public_url AS "public_url!",
+    archived
 FROM
     status_entry
 WHERE
@@ -164,16 +948,23 @@ WHERE
         )
     })?;
 
-    sqlx::query_as!(
+    let history = sqlx::query_as!(
         HistoryRow,
         r#"
 SELECT
-    status_code AS "status!",
-    created AS "poll_time!"
+    status_code AS status,
+    state AS "state: CheckState",
+    created AS "poll_time!",
+    latency_ms,
+    ttfb_ms,
+    error_kind AS "error_kind: ErrorKind",
+    error_message,
+    answers
 FROM
     status_history
 WHERE
     status_id = ?
+    AND created >= datetime('now', '-1 day')
 ORDER BY
     created DESC
 "#,
@@ -183,13 +974,151 @@ ORDER BY
     .await
     .map_err(|err| {
         leptos::logging::error!("Failed to load status history: {err:?}");
-        ServerFnError::ServerError("Failed to load status entry".to_owned())
-    })
-    .map(move |history| StatusDetails {
+        <ServerFnError>::ServerError("Failed to load status entry".to_owned())
+    })?;
+
+    let failed_assertions = sqlx::query_as!(
+        FailedAssertion,
+        r#"
+SELECT
+    assertion,
+    message
+FROM
+    status_assertion_failure
+WHERE
+    history_id = (
+        SELECT
+            id
+        FROM
+            status_history
+        WHERE
+            status_id = ?
+        ORDER BY
+            created DESC,
+            id DESC
+        LIMIT
+            1
+    )
+ORDER BY
+    id
+"#,
+        id
+    )
+    .fetch_all(db)
+    .await
+    .map_err(|err| {
+        leptos::logging::error!("Failed to load failed assertions: {err:?}");
+        <ServerFnError>::ServerError("Failed to load status entry".to_owned())
+    })?;
+
+    let certificate = sqlx::query_as!(
+        CertificateRow,
+        r#"
+SELECT
+    subject,
+    issuer,
+    sans,
+    not_before,
+    not_after
+FROM
+    tls_certificate
+WHERE
+    status_id = ?
+"#,
+        id
+    )
+    .fetch_optional(db)
+    .await
+    .map_err(|err| {
+        leptos::logging::error!("Failed to load certificate: {err:?}");
+        <ServerFnError>::ServerError("Failed to load status entry".to_owned())
+    })?;
+
+    let heartbeat = sqlx::query_as!(
+        HeartbeatRow,
+        r#"
+SELECT
+    last_ping,
+    message
+FROM
+    heartbeat
+WHERE
+    status_id = ?
+"#,
+        id
+    )
+    .fetch_optional(db)
+    .await
+    .map_err(|err| {
+        leptos::logging::error!("Failed to load heartbeat: {err:?}");
+        <ServerFnError>::ServerError("Failed to load status entry".to_owned())
+    })?;
+
+    let slo = sqlx::query_as!(
+        SloRow,
+        r#"
+SELECT
+    target,
+    window_secs,
+    down_ms,
+    budget_remaining,
+    burn_rate_1h,
+    burn_rate_6h,
+    burning_fast,
+    updated
+FROM
+    slo_status
+WHERE
+    status_id = ?
+"#,
+        id
+    )
+    .fetch_optional(db)
+    .await
+    .map_err(|err| {
+        leptos::logging::error!("Failed to load slo: {err:?}");
+        <ServerFnError>::ServerError("Failed to load status entry".to_owned())
+    })?;
+
+    let incidents = sqlx::query_as!(
+        IncidentRow,
+        r#"
+SELECT
+    started,
+    ended,
+    error_kind AS "error_kind: ErrorKind",
+    error_message
+FROM
+    incident
+WHERE
+    status_id = ?
+    AND (
+        started >= datetime('now', '-90 days')
+        OR ended IS NULL
+    )
+ORDER BY
+    started DESC
+"#,
+        id
+    )
+    .fetch_all(db)
+    .await
+    .map_err(|err| {
+        leptos::logging::error!("Failed to load incidents: {err:?}");
+        <ServerFnError>::ServerError("Failed to load status entry".to_owned())
+    })?;
+
+    Ok(StatusDetails {
         id,
         public_url: header.public_url,
         name: header.name,
+        archived: header.archived,
         history,
+        failed_assertions,
+        certificate,
+        heartbeat,
+        slo,
+        incidents,
     })
 }
 
@@ -217,6 +1146,7 @@ pub fn App() -> impl IntoView {
                 }>
                     <Route path=path!("") view=HomePage />
                     <Route path=path!("/site/:id") view=SiteDetails />
+                    <Route path=path!("/archived") view=ArchivedPage />
                 </Routes>
             </main>
         </Router>
@@ -327,8 +1257,20 @@ fn SiteDetails() -> impl IntoView {
                     }
                     Ok(d) => {
                         let last = d.history.first().cloned();
+                        let failed_assertions = d.failed_assertions.clone();
+                        let certificate = d.certificate.clone();
+                        let heartbeat = d.heartbeat.clone();
+                        let slo = d.slo.clone();
+                        let incidents = d.incidents.clone();
                         view! {
                             <h1 class="text-4xl">"Uptime "{d.name}</h1>
+                            {d
+                                .archived
+                                .map(|t| {
+                                    view! {
+                                        <div class="text-gray-600">"Archived at " {t.to_string()}</div>
+                                    }
+                                })}
                             <div class="font-medium text-blue-600 dark:text-blue-500 hover:underline">
                                 <a href=d.public_url.clone() target="_blank">
                                     {d.public_url.clone()}
@@ -340,11 +1282,39 @@ fn SiteDetails() -> impl IntoView {
                                         .map(|last| {
                                             view! {
                                                 <div>"Last fetch: " {last.poll_time.to_string()}</div>
-                                                <div>"Status: " {last.status.to_string()}</div>
+                                                <div>
+                                                    "Status: " {last.state.to_string()} " ("
+                                                    {format_status(last.status)} ")"
+                                                </div>
+                                                <div>"Latency: " {format_latency(last)}</div>
+                                                {last
+                                                    .answers
+                                                    .clone()
+                                                    .map(|answers| view! { <div>"Answers: " {answers}</div> })}
+                                                {last
+                                                    .error_summary()
+                                                    .map(|err| view! { <div>"Error: " {err}</div> })}
+                                                <ul class="list-disc list-inside">
+                                                    {failed_assertions
+                                                        .iter()
+                                                        .map(|f| {
+                                                            view! {
+                                                                <li class="text-red-600">
+                                                                    {f.assertion.clone()} ": " {f.message.clone()}
+                                                                </li>
+                                                            }
+                                                        })
+                                                        .collect_view()}
+                                                </ul>
                                             }
                                         })
                                 }}
                             </div>
+                            {certificate.map(certificate_details)}
+                            {heartbeat.map(heartbeat_details)}
+                            {slo.map(slo_details)}
+                            {incident_timeline(incidents)}
+                            <DailyRollups id=d.id />
                             <div>
                                 {d
                                     .history
@@ -371,6 +1341,200 @@ fn SiteDetails() -> impl IntoView {
     }
 }
 
+fn certificate_details(cert: CertificateRow) -> impl IntoView {
+    let days_left = (cert.not_after - chrono::Utc::now().naive_utc()).num_days();
+    view! {
+        <div class="px-5 py-3 my-2 rounded-lg bg-gray-100">
+            <h2 class="text-2xl">"Certificate"</h2>
+            <div>"Subject: " {cert.subject}</div>
+            <div>"Issuer: " {cert.issuer}</div>
+            <div>"Names: " {cert.sans}</div>
+            <div>"Valid from: " {cert.not_before.to_string()}</div>
+            <div>"Valid until: " {cert.not_after.to_string()} " (" {days_left} " days left)"</div>
+        </div>
+    }
+}
+
+#[component]
+fn DailyRollups(id: i64) -> impl IntoView {
+    let days = RwSignal::new(90u32);
+    let rollups = Resource::new(
+        move || days.get(),
+        move |days| get_rollups(id, RollupPeriod::Day, days),
+    );
+    let button = move |label: &'static str, value: u32| {
+        view! {
+            <button
+                class="px-2 rounded-lg"
+                class:bg-gray-300=move || days.get() == value
+                on:click=move |_| days.set(value)
+            >
+                {label}
+            </button>
+        }
+    };
+
+    view! {
+        <div class="px-5 py-3 my-2 rounded-lg bg-gray-100">
+            <h2 class="text-2xl">"Daily"</h2>
+            <div class="flex flex-row gap-2">{button("90 days", 90)} {button("1 year", 365)}</div>
+            <Transition fallback=LoadingSpinner>
+                {move || Suspend::new(async move {
+                    match rollups.await {
+                        Ok(rows) => {
+                            view! {
+                                <ul class="flex flex-row gap-1 flex-wrap">
+                                    {rows.iter().map(rollup_pip).collect_view()}
+                                </ul>
+                            }
+                                .into_any()
+                        }
+                        Err(err) => view! { <div>"Error " {err.to_string()}</div> }.into_any(),
+                    }
+                })}
+            </Transition>
+        </div>
+    }
+}
+
+fn rollup_pip(r: &RollupRow) -> impl IntoView {
+    const PIP: char = '\u{25AE}';
+
+    let availability = if r.checks > 0 {
+        100.0 * (r.checks - r.failures) as f64 / r.checks as f64
+    } else {
+        100.0
+    };
+    let color = if r.failures == 0 && r.degraded == 0 {
+        "text-green-500"
+    } else if availability >= 99.0 {
+        "text-yellow-500"
+    } else {
+        "text-red-500"
+    };
+    let latency = match (r.latency_avg_ms, r.latency_p95_ms) {
+        (Some(avg), Some(p95)) => format!("{avg:.0} ms, p95 {p95} ms"),
+        _ => "-".to_owned(),
+    };
+
+    view! {
+        <li class=color>
+            <span
+                class="cursor-default text-lg hover:text-3xl"
+                title=format!(
+                    "{} {availability:.2}% of {} checks up, {} failed, latency {latency}",
+                    r.bucket.date(),
+                    r.checks,
+                    r.failures,
+                )
+            >
+                {PIP}
+            </span>
+        </li>
+    }
+}
+
+fn heartbeat_details(heartbeat: HeartbeatRow) -> impl IntoView {
+    view! {
+        <div class="px-5 py-3 my-2 rounded-lg bg-gray-100">
+            <h2 class="text-2xl">"Heartbeat"</h2>
+            <div>
+                "Last ping: "
+                {heartbeat
+                    .last_ping
+                    .map(|t| t.to_string())
+                    .unwrap_or_else(|| "never".to_owned())}
+            </div>
+            {heartbeat.message.map(|msg| view! { <div>"Message: " {msg}</div> })}
+        </div>
+    }
+}
+
+fn slo_details(slo: SloRow) -> impl IntoView {
+    let remaining = slo.budget_remaining.clamp(0.0, 1.0) * 100.0;
+    let color = if slo.budget_remaining <= 0.0 {
+        "bg-red-500"
+    } else if slo.burning_fast || slo.budget_remaining < 0.25 {
+        "bg-yellow-500"
+    } else {
+        "bg-green-500"
+    };
+    let budget_min = slo.budget_ms() / 60_000.0;
+    let left_min = budget_min - slo.down_ms as f64 / 60_000.0;
+    let window_days = slo.window_secs as f64 / (24.0 * 60.0 * 60.0);
+
+    view! {
+        <div class="px-5 py-3 my-2 rounded-lg bg-gray-100">
+            <h2 class="text-2xl">"Error budget"</h2>
+            <div>{format!("Objective: {}% over {window_days:.0} days", slo.target)}</div>
+            <div class="w-full h-4 rounded-lg bg-gray-300">
+                <div class=format!("h-4 rounded-lg {color}") style=format!("width: {remaining:.1}%")></div>
+            </div>
+            <div>
+                {format!(
+                    "{:.1}% left, {left_min:.1} of {budget_min:.1} minutes",
+                    slo.budget_remaining * 100.0,
+                )}
+            </div>
+            <div>
+                {format!("Burn rate: {:.2} over 1h, {:.2} over 6h", slo.burn_rate_1h, slo.burn_rate_6h)}
+            </div>
+            {slo
+                .burning_fast
+                .then(|| {
+                    view! { <div class="text-red-600 font-bold">"Burning the budget too fast"</div> }
+                })}
+            <div class="text-sm">"Updated at " {slo.updated.to_string()}</div>
+        </div>
+    }
+}
+
+fn incident_timeline(incidents: Vec<IncidentRow>) -> impl IntoView {
+    let stats = IncidentStats::new(&incidents);
+    let format_stat =
+        |d: Option<chrono::TimeDelta>| d.map(format_duration).unwrap_or_else(|| "-".to_owned());
+
+    view! {
+        <div class="px-5 py-3 my-2 rounded-lg bg-gray-100">
+            <h2 class="text-2xl">"Incidents"</h2>
+            <div>
+                {format!(
+                    "{} in the last 90 days, MTTR {}, MTBF {}",
+                    incidents.len(),
+                    format_stat(stats.mttr),
+                    format_stat(stats.mtbf),
+                )}
+            </div>
+            <ol class="border-l-2 border-gray-400">
+                {incidents
+                    .into_iter()
+                    .map(|i| {
+                        let (color, duration) = match i.ended {
+                            Some(ended) => ("bg-green-500", format_duration(ended - i.started)),
+                            None => ("bg-red-500", "ongoing".to_owned()),
+                        };
+                        let reason = match (i.error_kind, i.error_message) {
+                            (Some(kind), Some(msg)) => format!("{kind}: {msg}"),
+                            (Some(kind), None) => kind.to_string(),
+                            _ => "-".to_owned(),
+                        };
+                        view! {
+                            <li class="ml-4 my-2">
+                                <div class=format!("absolute w-3 h-3 -ml-6 mt-1.5 rounded-full {color}")></div>
+                                <div class="text-sm">
+                                    {i.started.to_string()} " - "
+                                    {i.ended.map(|e| e.to_string()).unwrap_or_default()} " (" {duration} ")"
+                                </div>
+                                <div>{reason}</div>
+                            </li>
+                        }
+                    })
+                    .collect_view()}
+            </ol>
+        </div>
+    }
+}
+
 #[component]
 fn HomePage() -> impl IntoView {
     let statuses = Resource::new(|| (), |_| list_statuses());
@@ -389,6 +1553,7 @@ fn HomePage() -> impl IntoView {
                                     <tr>
                                         <th>Name</th>
                                         <th>Uptime</th>
+                                        <th>Latency</th>
                                         <th>Last ping</th>
                                     </tr>
                                 </thead>
@@ -405,6 +1570,59 @@ fn HomePage() -> impl IntoView {
                     })
             }}
         </Suspense>
+        <A href="/archived">
+            <div class="text-blue-600 underline">"Archived monitors"</div>
+        </A>
+    }
+}
+
+#[component]
+fn ArchivedPage() -> impl IntoView {
+    let archived = Resource::new(|| (), |_| list_archived());
+
+    view! {
+        <h1 class="text-4xl">Archived monitors</h1>
+        <p>"Add an entry with the same key to the config to restore a monitor with its history."</p>
+        <Suspense fallback=LoadingSpinner>
+            {move || {
+                archived
+                    .get()
+                    .map(|l| {
+                        let l = l.unwrap();
+                        view! {
+                            <table class="table-auto">
+                                <thead>
+                                    <tr>
+                                        <th>Name</th>
+                                        <th>Key</th>
+                                        <th>Archived</th>
+                                    </tr>
+                                </thead>
+                                <tbody>
+                                    {l
+                                        .into_iter()
+                                        .map(|a| {
+                                            view! {
+                                                <tr class="align-middle text-center">
+                                                    <td>
+                                                        <A href=format!("/site/{}", a.id)>
+                                                            <div class="text-blue-600 underline">{a.name}</div>
+                                                        </A>
+                                                    </td>
+                                                    <td>
+                                                        <code>{a.key}</code>
+                                                    </td>
+                                                    <td>{a.archived.to_string()}</td>
+                                                </tr>
+                                            }
+                                        })
+                                        .collect_view()}
+                                </tbody>
+                            </table>
+                        }
+                    })
+            }}
+        </Suspense>
     }
 }
 
@@ -413,16 +1631,14 @@ fn status_row(s: &[StatusRow]) -> impl IntoView {
     let first = s.first().cloned().unwrap();
     let link = format!("/site/{}", first.id);
     let public_url = &first.public_url;
-    let is_success = 200 <= first.last_status && first.last_status <= 299;
-    let is_redirect = 300 <= first.last_status && first.last_status <= 399;
-
-    let color = match (is_success, is_redirect) {
-        (false, true) => "bg-yellow-200",
-        (true, false) => "bg-green-200",
-        (false, false) => "bg-red-200",
-        (true, true) => {
-            unreachable!()
-        }
+    let latency = first
+        .latency_ms
+        .map(|l| format!("{l} ms"))
+        .unwrap_or_else(|| "-".to_owned());
+    let color = match first.state {
+        CheckState::Up => "bg-green-200",
+        CheckState::Degraded => "bg-yellow-200",
+        CheckState::Down => "bg-red-200",
     };
 
     view! {
@@ -438,8 +1654,45 @@ fn status_row(s: &[StatusRow]) -> impl IntoView {
                         "open"
                     </div>
                 </A>
+                {first
+                    .budget_remaining
+                    .map(|remaining| {
+                        let burning_fast = first.burning_fast.unwrap_or_default();
+                        view! {
+                            <span
+                                class="px-1 rounded-lg text-sm"
+                                class:bg-red-400=burning_fast || remaining <= 0.0
+                                title=if burning_fast {
+                                    "Error budget left, burning too fast"
+                                } else {
+                                    "Error budget left"
+                                }
+                            >
+                                {format!("{:.0}%", remaining * 100.0)}
+                            </span>
+                        }
+                    })}
+            </td>
+            <td>
+                {status_pip_list(s)}
+                <div class="flex flex-row gap-2 text-sm">
+                    {[
+                        ("24h", first.uptime_24h),
+                        ("7d", first.uptime_7d),
+                        ("30d", first.uptime_30d),
+                        ("90d", first.uptime_90d),
+                    ]
+                        .map(|(window, uptime)| {
+                            view! {
+                                <span title=format!("Uptime over {window}")>
+                                    {window}" "{format_uptime(uptime)}
+                                </span>
+                            }
+                        })
+                        .collect_view()}
+                </div>
             </td>
-            <td>{status_pip_list(s)}</td>
+            <td>{latency}</td>
             <td>{first.poll_time.to_string()}</td>
         </tr>
     }
@@ -453,7 +1706,13 @@ fn status_pip_list(s: &[StatusRow]) -> impl IntoView {
                 .map(|s| status_pip(
                     &HistoryRow {
                         status: s.last_status,
+                        state: s.state,
                         poll_time: s.poll_time,
+                        latency_ms: s.latency_ms,
+                        ttfb_ms: s.ttfb_ms,
+                        error_kind: s.error_kind,
+                        error_message: s.error_message.clone(),
+                        answers: None,
                     },
                 ))
                 .collect_view()}
@@ -461,26 +1720,61 @@ fn status_pip_list(s: &[StatusRow]) -> impl IntoView {
     }
 }
 
+fn format_duration(d: chrono::TimeDelta) -> String {
+    let secs = d.num_seconds();
+    let (days, hours, minutes) = (secs / 86400, secs / 3600 % 24, secs / 60 % 60);
+    if days > 0 {
+        format!("{days}d {hours}h")
+    } else if hours > 0 {
+        format!("{hours}h {minutes}m")
+    } else if minutes > 0 {
+        format!("{minutes}m {}s", secs % 60)
+    } else {
+        format!("{secs}s")
+    }
+}
+
+fn format_uptime(uptime: Option<f64>) -> String {
+    uptime
+        .map(|u| format!("{u:.2}%"))
+        .unwrap_or_else(|| "-".to_owned())
+}
+
+fn format_status(status: Option<i64>) -> String {
+    status
+        .map(|s| s.to_string())
+        .unwrap_or_else(|| "-".to_owned())
+}
+
+fn format_latency(s: &HistoryRow) -> String {
+    match (s.latency_ms, s.ttfb_ms) {
+        (Some(latency), Some(ttfb)) => format!("{latency} ms (first byte {ttfb} ms)"),
+        (Some(latency), None) => format!("{latency} ms"),
+        _ => "-".to_owned(),
+    }
+}
+
 fn status_pip(s: &HistoryRow) -> impl IntoView {
     const PIP: char = '\u{25AE}';
 
-    let is_success = 200 <= s.status && s.status <= 299;
-    let is_redirect = 300 <= s.status && s.status <= 399;
-
-    let color = match (is_success, is_redirect) {
-        (false, true) => "text-yellow-500",
-        (true, false) => "text-green-500",
-        (false, false) => "text-red-500",
-        (true, true) => {
-            unreachable!()
-        }
+    let color = match s.state {
+        CheckState::Up => "text-green-500",
+        CheckState::Degraded => "text-yellow-500",
+        CheckState::Down => "text-red-500",
     };
 
     view! {
         <li class=color>
             <span
                 class="cursor-default text-lg hover:text-3xl"
-                title=format!("{} Status: {}", s.poll_time.to_string(), s.status)
+                title=format!(
+                    "{} {} Status: {} Latency: {}{}",
+                    s.poll_time.to_string(),
+                    s.state,
+                    format_status(s.status),
+                    format_latency(s),
+                    s.error_summary().map(|err| format!(" Error: {err}")).unwrap_or_default(),
+                )
             >
                 {PIP}
             </span>
@@ -517,4 +1811,97 @@ entries = []
 
         assert!(config.poll_interval.is_none());
     }
+
+    #[cfg_attr(feature = "ssr", test)]
+    #[allow(unused)]
+    fn test_entry_interval_parsing() {
+        let config: Config = toml::from_str(
+            r#"
+[[entries]]
+name = "payments"
+public_url = "https://example.com"
+interval = "10s"
+
+[[entries]]
+name = "blog"
+public_url = "https://example.org"
+"#,
+        )
+        .expect("Failed to parse config");
+
+        assert_eq!(config.entries[0].interval, Some(Duration::from_secs(10)));
+        assert!(config.entries[1].interval.is_none());
+    }
+
+    #[cfg_attr(feature = "ssr", test)]
+    #[allow(unused)]
+    fn test_accepted_status_parsing() {
+        let config: Config = toml::from_str(
+            r#"
+[[entries]]
+name = "api"
+public_url = "https://example.com"
+accepted_status = ["2xx", 401]
+degraded_status = ["300-302"]
+
+[[entries]]
+name = "blog"
+public_url = "https://example.org"
+"#,
+        )
+        .expect("Failed to parse config");
+
+        let api = &config.entries[0];
+        assert_eq!(api.status_state(204), CheckState::Up);
+        assert_eq!(api.status_state(401), CheckState::Up);
+        assert_eq!(api.status_state(302), CheckState::Degraded);
+        assert_eq!(api.status_state(307), CheckState::Down);
+        assert_eq!(api.status_state(403), CheckState::Down);
+
+        let blog = &config.entries[1];
+        assert_eq!(blog.status_state(200), CheckState::Up);
+        assert_eq!(blog.status_state(307), CheckState::Degraded);
+        assert_eq!(blog.status_state(401), CheckState::Down);
+
+        assert!(toml::from_str::<Config>(
+            r#"
+[[entries]]
+name = "api"
+public_url = "https://example.com"
+accepted_status = ["20x"]
+"#,
+        )
+        .is_err());
+    }
+
+    #[cfg_attr(feature = "ssr", test)]
+    #[allow(unused)]
+    fn test_incident_stats() {
+        let at = |h| {
+            chrono::NaiveDate::from_ymd_opt(2026, 10, 18)
+                .unwrap()
+                .and_hms_opt(h, 0, 0)
+                .unwrap()
+        };
+        let incident = |started, ended: Option<u32>| IncidentRow {
+            started: at(started),
+            ended: ended.map(at),
+            error_kind: None,
+            error_message: None,
+        };
+        let stats = IncidentStats::new(&[
+            incident(10, None),
+            incident(6, Some(8)),
+            incident(1, Some(2)),
+        ]);
+        assert_eq!(stats.mttr, Some(chrono::TimeDelta::minutes(90)));
+        assert_eq!(stats.mtbf, Some(chrono::TimeDelta::hours(3)));
+        assert_eq!(
+            IncidentStats::new(&[]),
+            IncidentStats {
+                mttr: None,
+                mtbf: None
+            }
+        );
+    }
 }
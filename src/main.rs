@@ -1,27 +1,182 @@
 #[cfg(feature = "ssr")]
 #[derive(clap_derive::Parser)]
 struct Args {
-    #[clap(long, short, default_value = "uptime.toml")]
+    #[clap(long, short, default_value = "uptime.toml", global = true)]
     pub config: std::path::PathBuf,
+    #[clap(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[cfg(feature = "ssr")]
+#[derive(clap_derive::Subcommand)]
+enum Command {
+    /// Run the monitors and the web ui, the default
+    Serve,
+    /// Report every problem in the config file
+    Validate,
+    /// Run a single monitor once and print the result
+    Check {
+        /// Name, key or url of the entry
+        entry: String,
+    },
 }
 
 #[cfg(feature = "ssr")]
 #[tokio::main]
-async fn main() {
+async fn main() -> std::process::ExitCode {
+    use clap::Parser;
+
+    let args = Args::parse();
+    match args.command.unwrap_or(Command::Serve) {
+        Command::Serve => serve(&args.config).await,
+        Command::Validate => validate(&args.config),
+        Command::Check { entry } => check(&args.config, &entry).await,
+    }
+}
+
+/// Reads the config and prints its problems, `None` if it is invalid
+#[cfg(feature = "ssr")]
+fn read_config(path: &std::path::Path) -> Option<(String, uptime::app::Config)> {
+    let contents = match std::fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(err) => {
+            eprintln!("Failed to read {}: {err}", path.display());
+            return None;
+        }
+    };
+    let problems = uptime::status_check::validate_config(&contents);
+    for problem in &problems {
+        match problem.position {
+            Some(_) => eprintln!("{}:{problem}", path.display()),
+            None => eprintln!("{}: {problem}", path.display()),
+        }
+    }
+    if !problems.is_empty() {
+        return None;
+    }
+    let config = toml::from_str(&contents).expect("Validated config failed to parse");
+    Some((contents, config))
+}
+
+#[cfg(feature = "ssr")]
+fn validate(path: &std::path::Path) -> std::process::ExitCode {
+    match read_config(path) {
+        Some((_, config)) => {
+            println!(
+                "{} is valid, {} entries",
+                path.display(),
+                config.entries.len()
+            );
+            std::process::ExitCode::SUCCESS
+        }
+        None => std::process::ExitCode::FAILURE,
+    }
+}
+
+#[cfg(feature = "ssr")]
+async fn check(path: &std::path::Path, target: &str) -> std::process::ExitCode {
+    use std::process::ExitCode;
+
+    use uptime::app::CheckState;
+    use uptime::status_check::{Monitor, PollLimits, Poller};
+
+    let Some((_, config)) = read_config(path) else {
+        return ExitCode::FAILURE;
+    };
+    let target_url = url::Url::parse(target).ok();
+    let Some(entry) = config.entries.iter().find(|e| {
+        e.name == target
+            || e.key() == target
+            || target_url
+                .as_ref()
+                .is_some_and(|url| &e.public_url == url || e.polling_url.as_ref() == Some(url))
+    }) else {
+        eprintln!("No entry matches {target:?}");
+        return ExitCode::FAILURE;
+    };
+    let monitor = Monitor::new(0, entry, &config).expect("Validated entry is invalid");
+    if monitor.url().scheme() == "heartbeat" {
+        return print_ping_url(&config, entry).await;
+    }
+
+    // results of the check command are not stored, so the pool is never connected
+    let db = sqlx::SqlitePool::connect_lazy("sqlite::memory:").expect("Failed to create pool");
+    let poller = Poller::new(db, PollLimits::default()).expect("Failed to create poller");
+    let started = std::time::Instant::now();
+    let result = poller
+        .check(monitor)
+        .await
+        .expect("The monitor is not checked elsewhere");
+    println!("{result}");
+    println!("Took {} ms", started.elapsed().as_millis());
+    if result.state() == CheckState::Down {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+/// Prints the ping url of a heartbeat entry, the token is created by the server
+#[cfg(feature = "ssr")]
+async fn print_ping_url(
+    config: &uptime::app::Config,
+    entry: &uptime::app::Entry,
+) -> std::process::ExitCode {
+    use std::{process::ExitCode, str::FromStr as _};
+
+    use anyhow::Context as _;
+
+    println!(
+        "{} is a heartbeat monitor, it is checked by its pings",
+        entry.name
+    );
+    let db_url = std::env::var("DATABASE_URL").unwrap_or_else(|_| "uptime.db".to_owned());
+    let path: anyhow::Result<_> = async {
+        let opts = sqlx::sqlite::SqliteConnectOptions::from_str(&db_url)
+            .context("Failed to parse DATABASE_URL")?
+            .read_only(true);
+        let db = sqlx::SqlitePool::connect_with(opts)
+            .await
+            .context("Failed to open database")?;
+        uptime::status_check::ping_path(&db, entry.key()).await
+    }
+    .await;
+    match path {
+        Ok(Some(path)) => {
+            match &config.dashboard_url {
+                Some(base) => println!("Ping url: {}{path}", base.as_str().trim_end_matches('/')),
+                None => println!("Ping url: {path}"),
+            }
+            ExitCode::SUCCESS
+        }
+        Ok(None) => {
+            eprintln!("No ping url yet, it is created when the server starts with this entry");
+            ExitCode::FAILURE
+        }
+        Err(err) => {
+            eprintln!("{err:#}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(feature = "ssr")]
+async fn serve(config_path: &std::path::Path) -> std::process::ExitCode {
     use std::str::FromStr as _;
-    use std::time::Duration;
 
     use axum::Router;
-    use clap::Parser;
     use leptos::prelude::*;
     use leptos_axum::{generate_route_list, LeptosRoutes};
     use sqlx::sqlite::SqlitePoolOptions;
     use tower_http::compression::CompressionLayer;
     use tower_http::decompression::RequestDecompressionLayer;
     use tracing_subscriber::prelude::*;
-    use uptime::app::*;
+    use uptime::app::App;
     use uptime::fileserv::file_and_error_handler;
-    use uptime::status_check::poll_statuses;
+    use uptime::status_check::{
+        build_notifiers, deliver_notifications, ping_heartbeat, poll_statuses, prune_history,
+        update_rollups, update_slos, watch_config, PollLimits, Poller, Scheduler,
+    };
     use uptime::{app::ssr::AppState, status_check::init_statuses};
 
     tracing_subscriber::registry()
@@ -33,11 +188,9 @@ async fn main() {
         .try_init()
         .expect("Failed to init tracing");
 
-    let args = Args::parse();
-
-    let config = std::fs::read_to_string(&args.config).expect("Failed to read config file");
-
-    let config: Config = toml::from_str(&config).expect("Failed to parse config file");
+    let Some((contents, config)) = read_config(config_path) else {
+        return std::process::ExitCode::FAILURE;
+    };
 
     let db_url = std::env::var("DATABASE_URL").unwrap_or_else(|_| "uptime.db".to_owned());
 
@@ -62,13 +215,49 @@ async fn main() {
         .await
         .expect("Error running DB migrations");
 
-    init_statuses(&db, &config.entries)
+    let monitors = init_statuses(&db, &config)
         .await
         .expect("Failed to setup database");
-
-    let interval = config.poll_interval.unwrap_or(Duration::from_secs(30));
-    tracing::info!("Polling every {interval:?}");
-    tokio::spawn(poll_statuses(db.clone(), interval));
+    let defaults = PollLimits::default();
+    let limits = PollLimits {
+        max_in_flight: config.max_concurrency.unwrap_or(defaults.max_in_flight),
+        max_in_flight_per_host: config
+            .max_concurrency_per_host
+            .unwrap_or(defaults.max_in_flight_per_host),
+    };
+    tracing::info!(
+        ?limits,
+        retention = ?config.retention,
+        dashboard_url = config.dashboard_url.as_ref().map(|u| u.as_str()),
+        "Settings that a config reload does not change"
+    );
+    let poller = match Poller::new(db.clone(), limits) {
+        Ok(poller) => poller,
+        Err(err) => {
+            eprintln!("Failed to create poller: {err:#}");
+            return std::process::ExitCode::FAILURE;
+        }
+    };
+    let (monitors_tx, monitors_rx) = tokio::sync::watch::channel(monitors.clone());
+    tokio::spawn(poll_statuses(poller, Scheduler::new(monitors), monitors_rx));
+    tokio::spawn(update_rollups(db.clone(), monitors_tx.subscribe()));
+    tokio::spawn(update_slos(db.clone(), monitors_tx.subscribe()));
+    tokio::spawn(prune_history(db.clone(), config.retention));
+    let notifiers = build_notifiers(&config.notifiers).expect("Failed to create notifiers");
+    let (notifiers_tx, notifiers_rx) = tokio::sync::watch::channel(notifiers);
+    tokio::spawn(deliver_notifications(
+        db.clone(),
+        notifiers_rx,
+        config.dashboard_url.clone(),
+        monitors_tx.subscribe(),
+    ));
+    tokio::spawn(watch_config(
+        config_path.to_owned(),
+        contents,
+        db.clone(),
+        monitors_tx,
+        notifiers_tx,
+    ));
 
     // Setting get_configuration(None) means we'll be using cargo-leptos's env values
     // For deployment these variables are:
@@ -84,6 +273,10 @@ async fn main() {
     let state = AppState { db, leptos_options };
 
     let app = Router::new()
+        .route(
+            "/heartbeat/{token}",
+            axum::routing::get(ping_heartbeat).post(ping_heartbeat),
+        )
         .leptos_routes(&state, routes, App)
         .fallback(file_and_error_handler)
         .with_state(state)
@@ -96,6 +289,7 @@ async fn main() {
         .with_graceful_shutdown(shutdown_signal())
         .await
         .unwrap();
+    std::process::ExitCode::SUCCESS
 }
 
 #[cfg(feature = "ssr")]
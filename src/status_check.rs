@@ -1,137 +1,1084 @@
-use std::{collections::HashMap, time::Duration};
+use std::{
+    collections::{HashMap, HashSet},
+    hash::BuildHasher,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
 
 use anyhow::Context;
 use sqlx::{Acquire, SqlitePool};
+use tokio::{
+    sync::{watch, Semaphore},
+    task::JoinSet,
+    time::Instant,
+};
 use tracing::{debug, error, info};
 
-use crate::app::Entry;
+use crate::app::{CheckState, Config, Entry, ErrorKind};
 
-pub async fn init_statuses(db: &SqlitePool, entries: &[Entry]) -> anyhow::Result<()> {
+mod assertions;
+mod dns;
+mod heartbeat;
+mod http;
+mod incident;
+mod notify;
+mod reload;
+mod retention;
+mod rollup;
+mod slo;
+mod tcp;
+mod tls;
+mod validate;
+
+use assertions::{AssertionFailure, Assertions};
+use dns::DnsTarget;
+use heartbeat::Heartbeat;
+pub use heartbeat::{ping_heartbeat, ping_path};
+use http::HttpRequest;
+pub use notify::{
+    build_notifiers, deliver_notifications, NotificationKind, Notifier, Notifiers, Policy,
+    StateChange,
+};
+pub use reload::watch_config;
+pub use retention::prune_history;
+pub use rollup::update_rollups;
+pub use slo::update_slos;
+use tcp::TcpTarget;
+use tls::CertificateInfo;
+pub use validate::{validate_config, ConfigProblem};
+
+const DEFAULT_INTERVAL: Duration = Duration::from_secs(30);
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+const DEFAULT_RETRY_DELAY: Duration = Duration::from_secs(1);
+const DEFAULT_CERT_EXPIRY_DAYS: u32 = 14;
+const MIN_INTERVAL: Duration = Duration::from_secs(1);
+const MIN_SLO_WINDOW: Duration = Duration::from_secs(24 * 60 * 60);
+
+#[derive(Debug, Clone)]
+pub struct Monitor {
+    pub id: i64,
+    pub entry: Arc<Entry>,
+    pub interval: Duration,
+    pub timeout: Duration,
+    pub retries: u32,
+    pub retry_delay: Duration,
+    pub cert_expiry_days: u32,
+    /// Notifiers that get the state changes, all of them if None
+    pub notify: Option<Arc<[String]>>,
+    pub policy: Policy,
+    kind: Arc<CheckKind>,
+    assertions: Arc<Assertions>,
+}
+
+/// What a monitor checks, decided by the scheme of its polling url
+#[derive(Debug)]
+enum CheckKind {
+    Http(HttpRequest),
+    Tcp(TcpTarget),
+    Dns(Box<DnsTarget>),
+    Heartbeat(Heartbeat),
+}
+
+impl CheckKind {
+    fn from_entry(entry: &Entry, url: &url::Url, timeout: Duration) -> anyhow::Result<Self> {
+        match url.scheme() {
+            "http" | "https" => HttpRequest::from_entry(entry).map(Self::Http),
+            "tcp" => TcpTarget::from_url(url).map(Self::Tcp),
+            "dns" => DnsTarget::from_entry(entry, url, timeout).map(|t| Self::Dns(Box::new(t))),
+            "heartbeat" => Heartbeat::from_entry(entry).map(Self::Heartbeat),
+            scheme => anyhow::bail!("Unsupported url scheme {scheme:?}"),
+        }
+    }
+}
+
+impl Monitor {
+    pub fn new(id: i64, entry: &Entry, config: &Config) -> anyhow::Result<Self> {
+        let url = entry.polling_url.as_ref().unwrap_or(&entry.public_url);
+        let timeout = entry.timeout.or(config.timeout).unwrap_or(DEFAULT_TIMEOUT);
+        if let Some(slo) = &entry.slo {
+            anyhow::ensure!(
+                slo.target > 0.0 && slo.target < 100.0,
+                "slo target must be a percentage between 0 and 100, got {}",
+                slo.target
+            );
+            // the budget is read from the hourly rollups
+            anyhow::ensure!(
+                slo.window() >= MIN_SLO_WINDOW,
+                "slo window must be at least a day"
+            );
+        }
+        let group = entry
+            .group
+            .as_ref()
+            .map(|group| {
+                config
+                    .groups
+                    .get(group)
+                    .with_context(|| format!("Unknown group {group:?}"))
+            })
+            .transpose()?;
+        let notify = entry
+            .notify
+            .as_ref()
+            .or(group.map(|g| &g.notify))
+            .map(|names| {
+                for name in names {
+                    anyhow::ensure!(
+                        config.notifiers.iter().any(|n| &n.name == name),
+                        "Unknown notifier {name:?}"
+                    );
+                }
+                Ok(Arc::from(names.as_slice()))
+            })
+            .transpose()?;
+        Ok(Self {
+            id,
+            entry: Arc::new(entry.clone()),
+            interval: entry
+                .interval
+                .or(config.poll_interval)
+                .unwrap_or(DEFAULT_INTERVAL)
+                .max(MIN_INTERVAL),
+            timeout,
+            retries: entry.retries.or(config.retries).unwrap_or(0),
+            retry_delay: config.retry_delay.unwrap_or(DEFAULT_RETRY_DELAY),
+            cert_expiry_days: entry
+                .cert_expiry_days
+                .or(config.cert_expiry_days)
+                .unwrap_or(DEFAULT_CERT_EXPIRY_DAYS),
+            notify,
+            policy: Policy::new(entry, config)?,
+            kind: Arc::new(CheckKind::from_entry(entry, url, timeout)?),
+            assertions: Arc::new(Assertions::from_entry(entry)?),
+        })
+    }
+
+    pub fn url(&self) -> &url::Url {
+        self.entry
+            .polling_url
+            .as_ref()
+            .unwrap_or(&self.entry.public_url)
+    }
+}
+
+pub async fn init_statuses(db: &SqlitePool, config: &Config) -> anyhow::Result<Vec<Monitor>> {
+    let entries = &config.entries;
     let mut tr = db.begin().await.context("Failed to start transaction")?;
     let conn = tr
         .acquire()
         .await
         .context("Failed to acquire db connection")?;
 
-    let existing_entries = sqlx::query!(r#"SELECT id, name FROM status_entry"#)
-        .fetch_all(&mut *conn)
-        .await
-        .context("Failed to fetch existing entries")?;
+    let existing_entries = sqlx::query!(
+        r#"
+SELECT
+    id,
+    key
+FROM
+    status_entry
+WHERE
+    archived IS NULL
+"#
+    )
+    .fetch_all(&mut *conn)
+    .await
+    .context("Failed to fetch existing entries")?;
 
     let mut existing_entries = existing_entries
         .into_iter()
-        .map(|r| (r.name, r.id))
+        .map(|r| (r.key, r.id))
         .collect::<HashMap<String, i64>>();
 
+    let mut keys = HashSet::with_capacity(entries.len());
+    let mut monitors = Vec::with_capacity(entries.len());
     for entry in entries {
         let name = entry.name.as_str();
-        existing_entries.remove(name);
+        let key = entry.key();
+        anyhow::ensure!(keys.insert(key), "Duplicate entry key {key:?}");
+        existing_entries.remove(key);
         let public_url = entry.public_url.as_str();
         let internal_url = entry.polling_url.as_ref().map(|x| x.as_str());
-        sqlx::query!(
+        // entries coming back to the config are restored with their history
+        let row = sqlx::query!(
             r#"
 INSERT INTO
-    status_entry (name, public_url, internal_url)
+    status_entry (key, name, public_url, internal_url)
 VALUES
-    ($1, $2, $3) ON CONFLICT DO
+    ($1, $2, $3, $4) ON CONFLICT (key) DO
 UPDATE
 SET
-    public_url = $2,
-    internal_url = $3
+    name = $2,
+    public_url = $3,
+    internal_url = $4,
+    archived = NULL
+RETURNING
+    id
 "#,
+            key,
             name,
             public_url,
             internal_url
         )
-        .execute(&mut *conn)
+        .fetch_one(&mut *conn)
         .await
         .with_context(|| format!("Failed to insert entry {}", entry.name))?;
+
+        let monitor = Monitor::new(row.id, entry, config)
+            .with_context(|| format!("Invalid entry {}", entry.name))?;
+        if let CheckKind::Heartbeat(_) = monitor.kind.as_ref() {
+            let token = heartbeat::create_token(&mut *conn, row.id)
+                .await
+                .with_context(|| format!("Failed to setup heartbeat of {}", entry.name))?;
+            // logged once as the token is a secret, `uptime check <name>` prints it again
+            if let Some(token) = token {
+                info!(
+                    name,
+                    url = format!("/heartbeat/{token}"),
+                    "Created heartbeat ping url"
+                );
+            }
+        }
+        monitors.push(monitor);
     }
 
-    for (name, id) in existing_entries {
-        debug!(name, id, "Removing missing entry");
+    for (key, id) in existing_entries {
+        info!(key, id, "Archiving missing entry");
         sqlx::query!(
             r#"
-DELETE FROM
-    status_history
-WHERE
-    status_id = $1;
-
-DELETE FROM
+UPDATE
     status_entry
+SET
+    archived = CURRENT_TIMESTAMP
 WHERE
-    id = $1;
+    id = $1
 "#,
-            id,
             id
         )
         .execute(&mut *conn)
         .await
-        .with_context(|| format!("Failed to delete missing entry {}", name))?;
+        .with_context(|| format!("Failed to archive missing entry {}", key))?;
     }
 
     tr.commit().await.context("Failed to commit transaction")?;
 
-    Ok(())
+    Ok(monitors)
 }
 
-// TODO: config interval
-pub async fn poll_statuses(db: SqlitePool, interval: Duration) -> anyhow::Result<()> {
-    loop {
-        info!("Polling site statuses");
-        if let Err(err) = poll_statuses_once(&db).await {
-            error!(?err, "Status poll failed");
+#[derive(Debug, Clone, Copy)]
+pub struct PollLimits {
+    pub max_in_flight: usize,
+    pub max_in_flight_per_host: usize,
+}
+
+impl Default for PollLimits {
+    fn default() -> Self {
+        Self {
+            max_in_flight: 32,
+            max_in_flight_per_host: 4,
         }
-        tokio::time::sleep(interval).await;
     }
 }
 
-pub async fn poll_statuses_once(db: &SqlitePool) -> anyhow::Result<()> {
-    let entries = sqlx::query!(
-        r#"
-SELECT
-    id,
-    coalesce(internal_url, public_url) AS url
-FROM
-    status_entry
-"#
-    )
-    .fetch_all(db)
-    .await
-    .context("Failed to fetch status entries")?;
+/// Number of check results written in a single transaction
+const WRITE_BATCH_SIZE: usize = 32;
+/// Pending results are flushed if no check finished for this long
+const WRITE_FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+/// Attempts to write the last results of a round, earlier failed writes are retried with the
+/// next flush
+const WRITE_ATTEMPTS: u32 = 3;
 
-    let mut tr = db.begin().await.context("Failed to begin transaction")?;
-    let conn = tr
-        .acquire()
-        .await
-        .context("Failed to acquire db connection")?;
-    for row in entries {
-        let resp = reqwest::get(&row.url).await;
-        let status_code = match resp {
-            Ok(resp) => resp.status().as_u16() as i64,
-            Err(err) => {
-                // TODO: record the error in the db
-                error!(?err, url = row.url, "Request failed");
-                -1
+#[derive(Debug)]
+pub struct CheckResult {
+    status_id: i64,
+    url: String,
+    state: CheckState,
+    status_code: Option<i64>,
+    latency_ms: Option<i64>,
+    ttfb_ms: Option<i64>,
+    error_kind: Option<ErrorKind>,
+    error_message: Option<String>,
+    assertion_failures: Vec<AssertionFailure>,
+    certificate: Option<CertificateInfo>,
+    /// Answer set of dns checks
+    answers: Option<String>,
+    policy: Policy,
+}
+
+impl CheckResult {
+    /// Down until a check of the monitor says otherwise
+    fn new(monitor: &Monitor) -> Self {
+        Self {
+            status_id: monitor.id,
+            url: monitor.url().to_string(),
+            state: CheckState::Down,
+            status_code: None,
+            latency_ms: None,
+            ttfb_ms: None,
+            error_kind: None,
+            error_message: None,
+            assertion_failures: Vec::new(),
+            certificate: None,
+            answers: None,
+            policy: monitor.policy,
+        }
+    }
+
+    pub fn state(&self) -> CheckState {
+        self.state
+    }
+
+    fn fail(&mut self, kind: ErrorKind, err: &dyn std::error::Error) {
+        debug!(?err, ?kind, url = self.url, "Check failed");
+        self.state = CheckState::Down;
+        self.error_kind = Some(kind);
+        self.error_message = Some(error_chain(err));
+    }
+
+    fn fail_assertions(&mut self, failures: Vec<AssertionFailure>) {
+        if failures.is_empty() {
+            return;
+        }
+        self.state = CheckState::Down;
+        self.error_kind = Some(ErrorKind::Assertion);
+        self.error_message = Some(
+            failures
+                .iter()
+                .map(|f| format!("{}: {}", f.assertion, f.message))
+                .collect::<Vec<_>>()
+                .join("; "),
+        );
+        self.assertion_failures = failures;
+    }
+}
+
+impl std::fmt::Display for CheckResult {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.url, self.state)?;
+        if let Some(status) = self.status_code {
+            write!(f, ", status {status}")?;
+        }
+        if let Some(latency) = self.latency_ms {
+            write!(f, ", {latency} ms")?;
+        }
+        if let Some(ttfb) = self.ttfb_ms {
+            write!(f, " (first byte after {ttfb} ms)")?;
+        }
+        if let Some(kind) = self.error_kind {
+            write!(f, "\n  {kind}")?;
+            if let Some(msg) = &self.error_message {
+                write!(f, ": {msg}")?;
             }
+        }
+        if let Some(answers) = &self.answers {
+            write!(f, "\n  answers: {answers}")?;
+        }
+        if let Some(cert) = &self.certificate {
+            write!(
+                f,
+                "\n  certificate {} valid until {}",
+                cert.subject, cert.not_after
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Classifies the io errors of connection attempts
+fn classify_io_error(err: &std::io::Error) -> Option<ErrorKind> {
+    match err.kind() {
+        std::io::ErrorKind::ConnectionRefused => Some(ErrorKind::ConnectionRefused),
+        std::io::ErrorKind::TimedOut => Some(ErrorKind::Timeout),
+        _ => None,
+    }
+}
+
+fn error_chain(err: &dyn std::error::Error) -> String {
+    let mut msg = err.to_string();
+    let mut source = err.source();
+    while let Some(e) = source {
+        msg.push_str(": ");
+        msg.push_str(&e.to_string());
+        source = e.source();
+    }
+    msg
+}
+
+#[derive(Debug, Clone)]
+pub struct Poller {
+    db: SqlitePool,
+    client: reqwest::Client,
+    limits: PollLimits,
+    in_flight: Arc<Semaphore>,
+    hosts: Arc<Mutex<HashMap<String, Arc<Semaphore>>>>,
+    /// Monitors with a check in progress
+    running: Arc<Mutex<HashSet<i64>>>,
+}
+
+/// Removes the monitor from the running set when its check is done
+struct RunningGuard {
+    id: i64,
+    running: Arc<Mutex<HashSet<i64>>>,
+}
+
+impl Drop for RunningGuard {
+    fn drop(&mut self) {
+        self.running.lock().unwrap().remove(&self.id);
+    }
+}
+
+impl Poller {
+    pub fn new(db: SqlitePool, limits: PollLimits) -> anyhow::Result<Self> {
+        anyhow::ensure!(
+            limits.max_in_flight > 0,
+            "max_concurrency must be at least 1"
+        );
+        anyhow::ensure!(
+            limits.max_in_flight_per_host > 0,
+            "max_concurrency_per_host must be at least 1"
+        );
+        let client = reqwest::Client::builder()
+            .user_agent(concat!("uptime/", env!("CARGO_PKG_VERSION")))
+            .tls_info(true)
+            .build()
+            .context("Failed to build http client")?;
+        Ok(Self {
+            db,
+            client,
+            limits,
+            in_flight: Arc::new(Semaphore::new(limits.max_in_flight)),
+            hosts: Default::default(),
+            running: Default::default(),
+        })
+    }
+
+    fn start_running(&self, id: i64) -> Option<RunningGuard> {
+        self.running
+            .lock()
+            .unwrap()
+            .insert(id)
+            .then(|| RunningGuard {
+                id,
+                running: self.running.clone(),
+            })
+    }
+
+    fn host_semaphore(&self, url: &str) -> Arc<Semaphore> {
+        let host = url::Url::parse(url)
+            .ok()
+            .and_then(|u| u.host_str().map(|h| h.to_owned()))
+            .unwrap_or_else(|| url.to_owned());
+        let mut hosts = self.hosts.lock().unwrap();
+        hosts
+            .entry(host)
+            .or_insert_with(|| Arc::new(Semaphore::new(self.limits.max_in_flight_per_host)))
+            .clone()
+    }
+
+    /// Checks the monitor with its retries, `None` if a check of the monitor is already running
+    pub async fn check(&self, monitor: Monitor) -> Option<CheckResult> {
+        let Some(_running) = self.start_running(monitor.id) else {
+            debug!(
+                name = monitor.entry.name,
+                "Previous check is still running, skipping"
+            );
+            return None;
         };
+        let mut attempt = 0;
+        loop {
+            let result = self.check_once(&monitor).await;
+            if result.state != CheckState::Down || attempt >= monitor.retries {
+                return Some(result);
+            }
+            attempt += 1;
+            debug!(
+                name = monitor.entry.name,
+                attempt,
+                error = ?result.error_kind,
+                "Check failed, retrying"
+            );
+            tokio::time::sleep(monitor.retry_delay).await;
+        }
+    }
 
-        sqlx::query!(
-            r#"
+    async fn check_once(&self, monitor: &Monitor) -> CheckResult {
+        let mut result = CheckResult::new(monitor);
+
+        // take the host permit first so tasks waiting on a busy host do not hold global permits
+        let host = self.host_semaphore(&result.url);
+        let _host_permit = host.acquire_owned().await.expect("semaphore closed");
+        let _permit = self
+            .in_flight
+            .clone()
+            .acquire_owned()
+            .await
+            .expect("semaphore closed");
+
+        match monitor.kind.as_ref() {
+            CheckKind::Http(request) => {
+                http::check(&self.client, monitor, request, &mut result).await
+            }
+            CheckKind::Tcp(target) => tcp::check(target, monitor.timeout, &mut result).await,
+            CheckKind::Dns(target) => dns::check(target, &mut result).await,
+            CheckKind::Heartbeat(heartbeat) => {
+                heartbeat::check(&self.db, monitor.id, heartbeat, &mut result).await
+            }
+        }
+        result
+    }
+
+    async fn write_results(&self, results: &mut Vec<CheckResult>) -> anyhow::Result<()> {
+        if results.is_empty() {
+            return Ok(());
+        }
+        let mut tr = self
+            .db
+            .begin()
+            .await
+            .context("Failed to begin transaction")?;
+        let conn = tr
+            .acquire()
+            .await
+            .context("Failed to acquire db connection")?;
+        for row in results.iter() {
+            let history = sqlx::query!(
+                r#"
 INSERT INTO
-    status_history (status_id, status_code)
+    status_history (
+        status_id,
+        state,
+        status_code,
+        latency_ms,
+        ttfb_ms,
+        error_kind,
+        error_message,
+        answers
+    )
 VALUES
-    (?, ?)
+    (?, ?, ?, ?, ?, ?, ?, ?)
+RETURNING
+    id,
+    created AS "created!: chrono::NaiveDateTime"
             "#,
-            row.id,
-            status_code
+                row.status_id,
+                row.state,
+                row.status_code,
+                row.latency_ms,
+                row.ttfb_ms,
+                row.error_kind,
+                row.error_message,
+                row.answers
+            )
+            .fetch_one(&mut *conn)
+            .await
+            .with_context(|| {
+                format!(
+                    "Failed to insert history entry for {} {}",
+                    row.status_id, row.url
+                )
+            })?;
+
+            notify::record_change(&mut *conn, row, history.id, history.created)
+                .await
+                .with_context(|| {
+                    format!(
+                        "Failed to record state change for {} {}",
+                        row.status_id, row.url
+                    )
+                })?;
+            incident::record(&mut *conn, row, history.created)
+                .await
+                .with_context(|| {
+                    format!(
+                        "Failed to record incident for {} {}",
+                        row.status_id, row.url
+                    )
+                })?;
+
+            for failure in &row.assertion_failures {
+                sqlx::query!(
+                    r#"
+INSERT INTO
+    status_assertion_failure (history_id, assertion, message)
+VALUES
+    (?, ?, ?)
+                "#,
+                    history.id,
+                    failure.assertion,
+                    failure.message
+                )
+                .execute(&mut *conn)
+                .await
+                .with_context(|| {
+                    format!(
+                        "Failed to insert assertion failure for {} {}",
+                        row.status_id, row.url
+                    )
+                })?;
+            }
+
+            if let Some(cert) = &row.certificate {
+                let sans = cert.sans.join(", ");
+                sqlx::query!(
+                    r#"
+INSERT INTO
+    tls_certificate (status_id, subject, issuer, sans, not_before, not_after)
+VALUES
+    ($1, $2, $3, $4, $5, $6) ON CONFLICT DO
+UPDATE
+SET
+    subject = $2,
+    issuer = $3,
+    sans = $4,
+    not_before = $5,
+    not_after = $6,
+    checked = CURRENT_TIMESTAMP
+                "#,
+                    row.status_id,
+                    cert.subject,
+                    cert.issuer,
+                    sans,
+                    cert.not_before,
+                    cert.not_after
+                )
+                .execute(&mut *conn)
+                .await
+                .with_context(|| {
+                    format!(
+                        "Failed to insert certificate for {} {}",
+                        row.status_id, row.url
+                    )
+                })?;
+            }
+        }
+        tr.commit()
+            .await
+            .context("Failed to commit the transaction")?;
+        results.clear();
+        Ok(())
+    }
+
+    pub async fn poll_statuses_once(&self, monitors: &[Monitor]) -> anyhow::Result<()> {
+        let mut checks = JoinSet::new();
+        for monitor in monitors {
+            let poller = self.clone();
+            let monitor = monitor.clone();
+            checks.spawn(async move { poller.check(monitor).await });
+        }
+
+        let mut results = Vec::with_capacity(WRITE_BATCH_SIZE);
+        loop {
+            let flush = match tokio::time::timeout(WRITE_FLUSH_INTERVAL, checks.join_next()).await {
+                Ok(Some(Ok(result))) => {
+                    results.extend(result);
+                    results.len() >= WRITE_BATCH_SIZE
+                }
+                Ok(Some(Err(err))) => {
+                    error!(?err, "Status check task failed");
+                    false
+                }
+                Ok(None) => break,
+                Err(_elapsed) => true,
+            };
+            if flush {
+                // the results are kept and written with the next flush
+                if let Err(err) = self.write_results(&mut results).await {
+                    error!(
+                        ?err,
+                        count = results.len(),
+                        "Failed to write status results"
+                    );
+                }
+            }
+        }
+        let mut attempt = 1;
+        loop {
+            match self.write_results(&mut results).await {
+                Ok(()) => return Ok(()),
+                Err(err) if attempt < WRITE_ATTEMPTS => {
+                    error!(
+                        ?err,
+                        count = results.len(),
+                        "Failed to write status results"
+                    );
+                    attempt += 1;
+                    tokio::time::sleep(WRITE_FLUSH_INTERVAL).await;
+                }
+                Err(err) => return Err(err),
+            }
+        }
+    }
+}
+
+/// Upper bound of the random delay before the first check of a monitor
+const MAX_START_JITTER: Duration = Duration::from_secs(30);
+
+#[derive(Debug)]
+struct Scheduled {
+    monitor: Monitor,
+    next_due: Instant,
+}
+
+/// Keeps track of when each monitor is due next
+#[derive(Debug)]
+pub struct Scheduler {
+    monitors: Vec<Scheduled>,
+}
+
+impl Scheduler {
+    pub fn new(monitors: Vec<Monitor>) -> Self {
+        let mut scheduler = Self {
+            monitors: Vec::new(),
+        };
+        scheduler.update(monitors);
+        scheduler
+    }
+
+    /// Replaces the monitors, keeping the schedule of the ones whose interval did not change
+    pub fn update(&mut self, monitors: Vec<Monitor>) {
+        let now = Instant::now();
+        let jitter = std::hash::RandomState::new();
+        let previous = self
+            .monitors
+            .drain(..)
+            .map(|s| (s.monitor.id, (s.monitor.interval, s.next_due)))
+            .collect::<HashMap<_, _>>();
+        self.monitors = monitors
+            .into_iter()
+            .map(|monitor| {
+                let next_due = match previous.get(&monitor.id) {
+                    Some(&(interval, next_due)) if interval == monitor.interval => next_due,
+                    _ => {
+                        // stagger the first checks so monitors don't all fire at once
+                        let max_jitter = monitor.interval.min(MAX_START_JITTER).as_millis() as u64;
+                        now + Duration::from_millis(jitter.hash_one(monitor.id) % max_jitter)
+                    }
+                };
+                Scheduled { monitor, next_due }
+            })
+            .collect();
+    }
+
+    pub fn next_due(&self) -> Option<Instant> {
+        self.monitors.iter().map(|s| s.next_due).min()
+    }
+
+    /// Returns the monitors that are due at `now` and schedules their next check
+    pub fn take_due(&mut self, now: Instant) -> Vec<Monitor> {
+        self.monitors
+            .iter_mut()
+            .filter(|s| s.next_due <= now)
+            .map(|s| {
+                s.next_due = next_due_after(s.next_due, s.monitor.interval, now);
+                s.monitor.clone()
+            })
+            .collect()
+    }
+}
+
+/// Next slot on the `due + k * interval` grid that is after `now`, so late rounds don't cause drift
+fn next_due_after(due: Instant, interval: Duration, now: Instant) -> Instant {
+    let next = due + interval;
+    if next > now {
+        return next;
+    }
+    let missed = (now - next).as_nanos() / interval.as_nanos() + 1;
+    next + Duration::from_nanos((interval.as_nanos() * missed) as u64)
+}
+
+/// Runs the checks as they become due, `updates` replaces the monitors when the config is reloaded
+pub async fn poll_statuses(
+    poller: Poller,
+    mut scheduler: Scheduler,
+    mut updates: watch::Receiver<Vec<Monitor>>,
+) -> anyhow::Result<()> {
+    let mut watching = true;
+    loop {
+        let due = scheduler.next_due();
+        if due.is_none() {
+            if !watching {
+                info!("No monitors configured");
+                return Ok(());
+            }
+            debug!("No monitors configured, waiting for a config reload");
+        }
+        let sleep = async {
+            match due {
+                Some(due) => tokio::time::sleep_until(due).await,
+                None => std::future::pending().await,
+            }
+        };
+        tokio::select! {
+            _ = sleep => {}
+            changed = updates.changed(), if watching => {
+                match changed {
+                    Ok(()) => scheduler.update(updates.borrow_and_update().clone()),
+                    Err(_) => watching = false,
+                }
+                continue;
+            }
+        }
+
+        let monitors = scheduler.take_due(Instant::now());
+        debug!(count = monitors.len(), "Polling site statuses");
+        let poller = poller.clone();
+        // a long round must not hold up the monitors that are due next
+        tokio::spawn(async move {
+            if let Err(err) = poller.poll_statuses_once(&monitors).await {
+                error!(?err, "Status poll failed");
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    use super::*;
+
+    /// Migrated in-memory database with a single entry of id 1
+    pub(super) async fn memory_db() -> SqlitePool {
+        // a single connection, every connection to :memory: opens another database
+        let db = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        sqlx::migrate!("./migrations").run(&db).await.unwrap();
+        sqlx::query(
+            "INSERT INTO status_entry (key, name, public_url) VALUES ('a', 'a', 'tcp://a')",
         )
-        .execute(&mut *conn)
+        .execute(&db)
         .await
-        .with_context(|| format!("Failed to insert history entry for {} {}", row.id, row.url))?;
+        .unwrap();
+        db
+    }
+
+    /// Monitor of the entry with id 1
+    pub(super) fn test_monitor(public_url: &str) -> Monitor {
+        let config: Config = toml::from_str(&format!(
+            "[[entries]]\nname = \"a\"\npublic_url = \"{public_url}\"\n"
+        ))
+        .unwrap();
+        Monitor::new(1, &config.entries[0], &config).unwrap()
     }
-    tr.commit()
+
+    #[tokio::test]
+    async fn test_init_statuses_keeps_history() {
+        let db = memory_db().await;
+        sqlx::query("INSERT INTO status_history (status_id, state) VALUES (1, 'up')")
+            .execute(&db)
+            .await
+            .unwrap();
+        let init = |entries: &str| {
+            let config: Config = toml::from_str(entries).unwrap();
+            let db = db.clone();
+            async move { init_statuses(&db, &config).await }
+        };
+        let entry = |id: i64| {
+            let db = db.clone();
+            async move {
+                sqlx::query_as::<_, (String, bool, i64)>(
+                    r#"
+SELECT
+    name,
+    archived IS NOT NULL,
+    (SELECT count(*) FROM status_history WHERE status_id = status_entry.id)
+FROM
+    status_entry
+WHERE
+    id = $1
+"#,
+                )
+                .bind(id)
+                .fetch_one(&db)
+                .await
+                .unwrap()
+            }
+        };
+
+        // renamed, the key keeps the row and its history
+        let monitors =
+            init("[[entries]]\nname = \"renamed\"\nkey = \"a\"\npublic_url = \"tcp://a:1\"\n")
+                .await
+                .unwrap();
+        assert_eq!(monitors[0].id, 1);
+        assert_eq!(entry(1).await, ("renamed".to_owned(), false, 1));
+
+        // removed, archived with its history
+        let monitors = init("[[entries]]\nname = \"b\"\npublic_url = \"tcp://b:1\"\n")
+            .await
+            .unwrap();
+        assert_ne!(monitors[0].id, 1);
+        assert_eq!(entry(1).await, ("renamed".to_owned(), true, 1));
+
+        // added back, restored
+        let monitors = init("[[entries]]\nname = \"a\"\npublic_url = \"tcp://a:1\"\n")
+            .await
+            .unwrap();
+        assert_eq!(monitors[0].id, 1);
+        assert_eq!(entry(1).await, ("a".to_owned(), false, 1));
+
+        let err = init(
+            "[[entries]]\nname = \"a\"\npublic_url = \"tcp://a:1\"\n\n[[entries]]\nname = \"b\"\nkey = \"a\"\npublic_url = \"tcp://b:1\"\n",
+        )
         .await
-        .context("Failed to commit the transaction")?;
-    Ok(())
+        .unwrap_err();
+        assert!(err.to_string().contains("Duplicate entry key"), "{err:#}");
+        assert_eq!(entry(1).await, ("a".to_owned(), false, 1));
+    }
+
+    #[test]
+    fn test_next_due_does_not_drift() {
+        let start = Instant::now();
+        let interval = Duration::from_secs(10);
+
+        // on time
+        let next = next_due_after(start, interval, start + Duration::from_secs(1));
+        assert_eq!(next, start + interval);
+
+        // the round ran long, but the next check stays on the original grid
+        let next = next_due_after(start, interval, start + Duration::from_secs(25));
+        assert_eq!(next, start + Duration::from_secs(30));
+
+        let next = next_due_after(start, interval, start + Duration::from_secs(30));
+        assert_eq!(next, start + Duration::from_secs(40));
+    }
+
+    #[test]
+    fn test_update_keeps_schedule() {
+        let config: Config = toml::from_str(
+            r#"
+[[entries]]
+name = "a"
+public_url = "tcp://localhost:1"
+interval = "10s"
+
+[[entries]]
+name = "b"
+public_url = "tcp://localhost:2"
+interval = "10s"
+"#,
+        )
+        .unwrap();
+        let monitor = |id, entry: &Entry| Monitor::new(id, entry, &config).unwrap();
+        let a = monitor(1, &config.entries[0]);
+        let b = monitor(2, &config.entries[1]);
+
+        let mut scheduler = Scheduler::new(vec![a.clone(), b.clone()]);
+        let due = |s: &Scheduler, id| {
+            s.monitors
+                .iter()
+                .find(|s| s.monitor.id == id)
+                .unwrap()
+                .next_due
+        };
+        let a_due = due(&scheduler, 1);
+
+        let mut slower = b.clone();
+        slower.interval = Duration::from_secs(60);
+        scheduler.update(vec![a, slower]);
+        assert_eq!(due(&scheduler, 1), a_due);
+        assert_eq!(scheduler.monitors.len(), 2);
+
+        scheduler.update(vec![b]);
+        assert_eq!(scheduler.monitors.len(), 1);
+        assert_eq!(scheduler.monitors[0].monitor.id, 2);
+    }
+
+    /// Http server that answers after a delay and records the most connections it had at once
+    async fn slow_server(
+        ip: &str,
+        current: Arc<std::sync::atomic::AtomicUsize>,
+        busiest: Arc<std::sync::atomic::AtomicUsize>,
+        total_current: Arc<std::sync::atomic::AtomicUsize>,
+        total_busiest: Arc<std::sync::atomic::AtomicUsize>,
+    ) -> std::net::SocketAddr {
+        use std::sync::atomic::Ordering;
+
+        use tokio::io::{AsyncReadExt as _, AsyncWriteExt as _};
+
+        let listener = tokio::net::TcpListener::bind((ip, 0)).await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            loop {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let now = current.fetch_add(1, Ordering::SeqCst) + 1;
+                busiest.fetch_max(now, Ordering::SeqCst);
+                let now = total_current.fetch_add(1, Ordering::SeqCst) + 1;
+                total_busiest.fetch_max(now, Ordering::SeqCst);
+                let current = current.clone();
+                let total_current = total_current.clone();
+                tokio::spawn(async move {
+                    let mut buf = [0; 1024];
+                    let _ = socket.read(&mut buf).await;
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                    // released before answering, so a waiting check is never counted early
+                    current.fetch_sub(1, Ordering::SeqCst);
+                    total_current.fetch_sub(1, Ordering::SeqCst);
+                    let _ = socket
+                        .write_all(
+                            b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                        )
+                        .await;
+                });
+            }
+        });
+        addr
+    }
+
+    #[tokio::test]
+    async fn test_poller_limits_concurrency() {
+        let counter = || Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let (total_current, total_busiest) = (counter(), counter());
+        let mut hosts = Vec::new();
+        for ip in ["127.0.0.1", "127.0.0.2"] {
+            let busiest = counter();
+            let addr = slow_server(
+                ip,
+                counter(),
+                busiest.clone(),
+                total_current.clone(),
+                total_busiest.clone(),
+            )
+            .await;
+            hosts.push((addr, busiest));
+        }
+
+        let entries = (0..8)
+            .map(|i| {
+                let (addr, _) = &hosts[i % 2];
+                format!("[[entries]]\nname = \"{i}\"\npublic_url = \"http://{addr}/\"\n")
+            })
+            .collect::<String>();
+        let config: Config = toml::from_str(&entries).unwrap();
+        let monitors = config
+            .entries
+            .iter()
+            .enumerate()
+            .map(|(id, entry)| Monitor::new(id as i64, entry, &config).unwrap())
+            .collect::<Vec<_>>();
+
+        let db = SqlitePool::connect_lazy("sqlite::memory:").unwrap();
+        let limits = PollLimits {
+            max_in_flight: 3,
+            max_in_flight_per_host: 2,
+        };
+        let poller = Poller::new(db, limits).unwrap();
+        let mut checks = JoinSet::new();
+        for monitor in monitors {
+            let poller = poller.clone();
+            checks.spawn(async move { poller.check(monitor).await });
+        }
+        while let Some(result) = checks.join_next().await {
+            assert_eq!(result.unwrap().unwrap().state(), CheckState::Up);
+        }
+
+        let busiest =
+            |c: &std::sync::atomic::AtomicUsize| c.load(std::sync::atomic::Ordering::SeqCst);
+        assert_eq!(busiest(&total_busiest), 3);
+        for (_, host_busiest) in &hosts {
+            assert_eq!(busiest(host_busiest), 2);
+        }
+    }
 }
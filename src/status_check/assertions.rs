@@ -0,0 +1,194 @@
+use anyhow::Context;
+use regex::Regex;
+
+use crate::app::{Entry, JsonAssertion, JsonCheck};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AssertionFailure {
+    pub assertion: String,
+    pub message: String,
+}
+
+impl AssertionFailure {
+    pub fn new(assertion: impl ToString, message: impl ToString) -> Self {
+        Self {
+            assertion: assertion.to_string(),
+            message: message.to_string(),
+        }
+    }
+}
+
+/// Checks run against the response body of an entry
+#[derive(Debug, Default)]
+pub struct Assertions {
+    contains: Vec<String>,
+    not_contains: Vec<String>,
+    matches: Option<Regex>,
+    json: Vec<JsonAssertion>,
+}
+
+impl Assertions {
+    pub fn from_entry(entry: &Entry) -> anyhow::Result<Self> {
+        let matches = entry
+            .body_matches
+            .as_deref()
+            .map(Regex::new)
+            .transpose()
+            .context("Invalid body_matches regex")?;
+        Ok(Self {
+            contains: entry.body_contains.clone(),
+            not_contains: entry.body_not_contains.clone(),
+            matches,
+            json: entry.json_assertions.clone(),
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.contains.is_empty()
+            && self.not_contains.is_empty()
+            && self.matches.is_none()
+            && self.json.is_empty()
+    }
+
+    /// Returns every failed assertion
+    pub fn check(&self, body: &[u8]) -> Vec<AssertionFailure> {
+        let mut failures = Vec::new();
+        let text = String::from_utf8_lossy(body);
+        for needle in &self.contains {
+            if !text.contains(needle.as_str()) {
+                failures.push(AssertionFailure::new(
+                    format!("body contains {needle:?}"),
+                    "not found",
+                ));
+            }
+        }
+        for needle in &self.not_contains {
+            if text.contains(needle.as_str()) {
+                failures.push(AssertionFailure::new(
+                    format!("body does not contain {needle:?}"),
+                    "found",
+                ));
+            }
+        }
+        if let Some(re) = &self.matches {
+            if !re.is_match(&text) {
+                failures.push(AssertionFailure::new(
+                    format!("body matches /{}/", re.as_str()),
+                    "no match",
+                ));
+            }
+        }
+        if !self.json.is_empty() {
+            match serde_json::from_slice::<serde_json::Value>(body) {
+                Ok(value) => failures.extend(
+                    self.json
+                        .iter()
+                        .filter_map(|assertion| check_json(assertion, &value)),
+                ),
+                Err(err) => failures.push(AssertionFailure::new(
+                    "body is JSON",
+                    format!("invalid JSON: {err}"),
+                )),
+            }
+        }
+        failures
+    }
+}
+
+fn check_json(assertion: &JsonAssertion, body: &serde_json::Value) -> Option<AssertionFailure> {
+    let value = body.pointer(&assertion.pointer);
+    let fail = |message: String| Some(AssertionFailure::new(assertion, message));
+    let found = || match value {
+        Some(v) => format!("found {v}"),
+        None => "missing".to_owned(),
+    };
+    match &assertion.check {
+        JsonCheck::Exists(exists) => {
+            if value.is_some() == *exists {
+                None
+            } else {
+                fail(found())
+            }
+        }
+        JsonCheck::Equals(expected) => {
+            if value == Some(expected) {
+                None
+            } else {
+                fail(found())
+            }
+        }
+        JsonCheck::GreaterThan(limit) => match value.and_then(|v| v.as_f64()) {
+            Some(n) if n > *limit => None,
+            _ => fail(found()),
+        },
+        JsonCheck::LessThan(limit) => match value.and_then(|v| v.as_f64()) {
+            Some(n) if n < *limit => None,
+            _ => fail(found()),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_body_assertions() {
+        let assertions = Assertions {
+            contains: vec!["Welcome".to_owned()],
+            not_contains: vec!["Error".to_owned()],
+            matches: Some(Regex::new(r"<title>.*Shop</title>").unwrap()),
+            json: vec![],
+        };
+
+        assert!(assertions
+            .check(b"<title>My Shop</title> Welcome!")
+            .is_empty());
+
+        let failures = assertions.check(b"<title>502 Bad Gateway</title> Error");
+        assert_eq!(
+            failures,
+            [
+                AssertionFailure::new(r#"body contains "Welcome""#, "not found"),
+                AssertionFailure::new(r#"body does not contain "Error""#, "found"),
+                AssertionFailure::new("body matches /<title>.*Shop</title>/", "no match"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_json_assertions() {
+        let entry: Entry = toml::from_str(
+            r#"
+name = "health"
+public_url = "https://example.com/health"
+json_assertions = [
+    { pointer = "/status", equals = "ok" },
+    { pointer = "/db", equals = "up" },
+    { pointer = "/cache", exists = true },
+    { pointer = "/queue/depth", less_than = 100 },
+]
+"#,
+        )
+        .unwrap();
+        let assertions = Assertions::from_entry(&entry).unwrap();
+
+        assert!(assertions
+            .check(br#"{"status":"ok","db":"up","cache":"up","queue":{"depth":3}}"#)
+            .is_empty());
+
+        let failures = assertions.check(br#"{"status":"ok","db":"down","queue":{"depth":300}}"#);
+        assert_eq!(
+            failures,
+            [
+                AssertionFailure::new(r#"/db equals "up""#, r#"found "down""#),
+                AssertionFailure::new("/cache exists", "missing"),
+                AssertionFailure::new("/queue/depth < 100", "found 300"),
+            ]
+        );
+
+        let failures = assertions.check(b"<html>");
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].assertion, "body is JSON");
+    }
+}
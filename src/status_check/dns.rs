@@ -0,0 +1,278 @@
+use std::{net::IpAddr, str::FromStr, time::Duration};
+
+use anyhow::Context;
+use hickory_resolver::{
+    config::{NameServerConfigGroup, ResolverConfig},
+    name_server::TokioConnectionProvider,
+    proto::{
+        rr::{RData, RecordType},
+        ProtoErrorKind,
+    },
+    Name, TokioResolver,
+};
+use tokio::time::Instant;
+
+use super::{assertions::AssertionFailure, CheckResult};
+use crate::app::{CheckState, Entry, ErrorKind};
+
+const DEFAULT_DNS_PORT: u16 = 53;
+
+/// Target of a `dns://[resolver[:port]]/name?type=A` monitor
+///
+/// The system resolver is used if the url has no resolver, e.g. `dns:///example.com?type=MX`
+#[derive(Debug)]
+pub struct DnsTarget {
+    pub name: Name,
+    pub record_type: RecordType,
+    /// Answers that must be present in the answer set
+    pub expected: Vec<String>,
+    resolver: TokioResolver,
+}
+
+impl DnsTarget {
+    pub fn from_entry(entry: &Entry, url: &url::Url, timeout: Duration) -> anyhow::Result<Self> {
+        let (name, record_type) = parse_query(url)?;
+        let mut builder = match resolver_addr(url)? {
+            Some(ip) => {
+                let port = url.port().unwrap_or(DEFAULT_DNS_PORT);
+                let config = ResolverConfig::from_parts(
+                    None,
+                    Vec::new(),
+                    NameServerConfigGroup::from_ips_clear(&[ip], port, true),
+                );
+                TokioResolver::builder_with_config(config, TokioConnectionProvider::default())
+            }
+            None => TokioResolver::builder_tokio()
+                .context("Failed to read the system resolver config")?,
+        };
+        let options = builder.options_mut();
+        options.timeout = timeout;
+        // retries are done by the poller, and every check should reach the resolver
+        options.attempts = 1;
+        options.cache_size = 0;
+
+        Ok(Self {
+            name,
+            record_type,
+            expected: entry.dns_answers.clone(),
+            resolver: builder.build(),
+        })
+    }
+}
+
+fn parse_query(url: &url::Url) -> anyhow::Result<(Name, RecordType)> {
+    let name = url.path().trim_start_matches('/');
+    anyhow::ensure!(!name.is_empty(), "dns url is missing the name to resolve");
+    let mut name = Name::from_str(name).with_context(|| format!("Invalid dns name {name:?}"))?;
+    // names in the url are absolute, search domains of the resolver do not apply
+    name.set_fqdn(true);
+
+    let record_type = match url.query_pairs().find(|(k, _)| k == "type") {
+        Some((_, t)) => {
+            let record_type = RecordType::from_str(&t.to_ascii_uppercase())
+                .with_context(|| format!("Invalid dns record type {t:?}"))?;
+            anyhow::ensure!(
+                matches!(
+                    record_type,
+                    RecordType::A
+                        | RecordType::AAAA
+                        | RecordType::CNAME
+                        | RecordType::MX
+                        | RecordType::TXT
+                ),
+                "Unsupported dns record type {record_type}"
+            );
+            record_type
+        }
+        None => RecordType::A,
+    };
+    Ok((name, record_type))
+}
+
+fn resolver_addr(url: &url::Url) -> anyhow::Result<Option<IpAddr>> {
+    match url.host() {
+        None => Ok(None),
+        Some(url::Host::Domain("")) => Ok(None),
+        Some(url::Host::Domain(host)) => host
+            .parse()
+            .map(Some)
+            .with_context(|| format!("dns resolver {host:?} must be an ip address")),
+        Some(url::Host::Ipv4(ip)) => Ok(Some(ip.into())),
+        Some(url::Host::Ipv6(ip)) => Ok(Some(ip.into())),
+    }
+}
+
+/// Answers are written without the trailing dot of names so they can be compared to the config
+fn format_answer(rdata: &RData) -> Option<String> {
+    let answer = match rdata {
+        RData::A(ip) => ip.to_string(),
+        RData::AAAA(ip) => ip.to_string(),
+        RData::CNAME(name) => name.to_string(),
+        RData::MX(mx) => format!("{} {}", mx.preference(), mx.exchange()),
+        RData::TXT(txt) => txt
+            .txt_data()
+            .iter()
+            .map(|s| String::from_utf8_lossy(s))
+            .collect(),
+        _ => return None,
+    };
+    Some(answer.trim_end_matches('.').to_owned())
+}
+
+/// Records the resolution time and the answer set, missing expected answers fail the check
+pub(super) async fn check(target: &DnsTarget, result: &mut CheckResult) {
+    let started = Instant::now();
+    let lookup = match target
+        .resolver
+        .lookup(target.name.clone(), target.record_type)
+        .await
+    {
+        Ok(lookup) => lookup,
+        Err(err) => {
+            let kind = match err.proto().map(|e| e.kind()) {
+                Some(ProtoErrorKind::Timeout) => ErrorKind::Timeout,
+                _ => ErrorKind::Dns,
+            };
+            return result.fail(kind, &err);
+        }
+    };
+    result.latency_ms = Some(started.elapsed().as_millis() as i64);
+
+    let mut answers = lookup
+        .record_iter()
+        .filter(|r| r.record_type() == target.record_type)
+        .filter_map(|r| format_answer(r.data()))
+        .collect::<Vec<_>>();
+    answers.sort();
+    answers.dedup();
+    result.state = CheckState::Up;
+
+    let failures = target
+        .expected
+        .iter()
+        .filter(|expected| {
+            let expected = expected.trim_end_matches('.');
+            !answers.iter().any(|a| a.eq_ignore_ascii_case(expected))
+        })
+        .map(|expected| {
+            AssertionFailure::new(format!("answer {expected}"), "not in the answer set")
+        })
+        .collect::<Vec<_>>();
+    result.answers = Some(answers.join(", "));
+    result.fail_assertions(failures);
+}
+
+#[cfg(test)]
+mod tests {
+    use hickory_resolver::proto::{
+        op::{Message, MessageType, ResponseCode},
+        rr::{rdata::A, Record},
+        serialize::binary::{BinDecodable, BinEncodable},
+    };
+    use tokio::net::UdpSocket;
+
+    use super::*;
+    use crate::{
+        app::Config,
+        status_check::{CheckKind, Monitor},
+    };
+
+    /// Answers `ok.test` with 192.0.2.1 and every other name with NXDOMAIN, returns the port
+    async fn dns_stub() -> u16 {
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let port = socket.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let mut buf = [0; 512];
+            loop {
+                let (len, peer) = socket.recv_from(&mut buf).await.unwrap();
+                let query = Message::from_bytes(&buf[..len]).unwrap();
+                let mut response = Message::new();
+                response
+                    .set_id(query.id())
+                    .set_message_type(MessageType::Response)
+                    .set_recursion_desired(true)
+                    .set_recursion_available(true)
+                    .add_queries(query.queries().to_vec());
+                let name = query.queries()[0].name().clone();
+                if name.to_string() == "ok.test." {
+                    response.add_answer(Record::from_rdata(
+                        name,
+                        60,
+                        RData::A(A::new(192, 0, 2, 1)),
+                    ));
+                } else {
+                    response.set_response_code(ResponseCode::NXDomain);
+                }
+                let bytes = response.to_bytes().unwrap();
+                socket.send_to(&bytes, peer).await.unwrap();
+            }
+        });
+        port
+    }
+
+    async fn check_stub(port: u16, name: &str, answer: &str) -> CheckResult {
+        let config: Config = toml::from_str(&format!(
+            r#"[[entries]]
+name = "dns"
+public_url = "dns://127.0.0.1:{port}/{name}"
+dns_answers = ["{answer}"]
+timeout = "2s"
+"#
+        ))
+        .unwrap();
+        let monitor = Monitor::new(1, &config.entries[0], &config).unwrap();
+        let CheckKind::Dns(target) = monitor.kind.as_ref() else {
+            panic!("not a dns monitor");
+        };
+        let mut result = CheckResult::new(&monitor);
+        check(target, &mut result).await;
+        result
+    }
+
+    #[tokio::test]
+    async fn test_check_against_stub() {
+        let port = dns_stub().await;
+
+        let result = check_stub(port, "ok.test", "192.0.2.1").await;
+        assert_eq!(result.state, CheckState::Up, "{result}");
+        assert_eq!(result.answers.as_deref(), Some("192.0.2.1"));
+
+        let result = check_stub(port, "ok.test", "192.0.2.9").await;
+        assert_eq!(result.state, CheckState::Down, "{result}");
+        assert_eq!(result.assertion_failures.len(), 1);
+        assert_eq!(result.answers.as_deref(), Some("192.0.2.1"));
+
+        let result = check_stub(port, "missing.test", "192.0.2.1").await;
+        assert_eq!(result.state, CheckState::Down, "{result}");
+        assert_eq!(result.error_kind, Some(ErrorKind::Dns));
+        assert_eq!(result.answers, None);
+    }
+
+    #[test]
+    fn test_parse_dns_url() {
+        let url = url::Url::parse("dns://127.0.0.1:5353/example.com?type=mx").unwrap();
+        let (name, record_type) = parse_query(&url).unwrap();
+        assert_eq!(name.to_string(), "example.com.");
+        assert_eq!(record_type, RecordType::MX);
+        assert_eq!(
+            resolver_addr(&url).unwrap(),
+            Some(IpAddr::from([127, 0, 0, 1]))
+        );
+
+        let url = url::Url::parse("dns:///example.com").unwrap();
+        let (_, record_type) = parse_query(&url).unwrap();
+        assert_eq!(record_type, RecordType::A);
+        assert_eq!(resolver_addr(&url).unwrap(), None);
+
+        let url = url::Url::parse("dns://[::1]/example.com?type=AAAA").unwrap();
+        assert_eq!(
+            resolver_addr(&url).unwrap(),
+            Some(IpAddr::from(std::net::Ipv6Addr::LOCALHOST))
+        );
+
+        let url = url::Url::parse("dns://resolver.local/example.com").unwrap();
+        assert!(resolver_addr(&url).is_err());
+        let url = url::Url::parse("dns:///example.com?type=SRV").unwrap();
+        assert!(parse_query(&url).is_err());
+    }
+}
@@ -0,0 +1,235 @@
+use std::time::Duration;
+
+use anyhow::Context;
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+};
+use chrono::NaiveDateTime;
+use serde_derive::Deserialize;
+use sqlx::{SqliteConnection, SqlitePool};
+use tracing::error;
+
+use super::CheckResult;
+use crate::app::{CheckState, Entry, ErrorKind};
+
+const DEFAULT_GRACE: Duration = Duration::from_secs(60);
+
+/// Expected schedule of a `heartbeat:` monitor, the job pings `/heartbeat/{token}`
+#[derive(Debug)]
+pub struct Heartbeat {
+    pub period: Duration,
+    pub grace: Duration,
+}
+
+impl Heartbeat {
+    pub fn from_entry(entry: &Entry) -> anyhow::Result<Self> {
+        let period = entry
+            .heartbeat_period
+            .context("heartbeat monitors need a heartbeat_period")?;
+        Ok(Self {
+            period,
+            grace: entry.heartbeat_grace.unwrap_or(DEFAULT_GRACE),
+        })
+    }
+}
+
+/// Generates the secret token of the ping url for new monitors, None if it exists already
+pub(super) async fn create_token(
+    conn: &mut SqliteConnection,
+    status_id: i64,
+) -> anyhow::Result<Option<String>> {
+    let row = sqlx::query!(
+        r#"
+INSERT INTO
+    heartbeat (status_id)
+VALUES
+    (?) ON CONFLICT DO NOTHING
+RETURNING
+    token
+"#,
+        status_id
+    )
+    .fetch_optional(&mut *conn)
+    .await
+    .context("Failed to insert heartbeat")?;
+    Ok(row.map(|r| r.token))
+}
+
+/// Path of the ping url of the entry, None until the server created its token
+pub async fn ping_path(db: &SqlitePool, key: &str) -> anyhow::Result<Option<String>> {
+    let row = sqlx::query!(
+        r#"
+SELECT
+    h.token
+FROM
+    heartbeat AS h
+    INNER JOIN status_entry AS se ON se.id = h.status_id
+WHERE
+    se.key = ?
+"#,
+        key
+    )
+    .fetch_optional(db)
+    .await
+    .context("Failed to fetch heartbeat token")?;
+    Ok(row.map(|r| format!("/heartbeat/{}", r.token)))
+}
+
+/// Reports the state of the last ping, or down if it is older than the period plus the grace
+pub(super) async fn check(
+    db: &SqlitePool,
+    status_id: i64,
+    heartbeat: &Heartbeat,
+    result: &mut CheckResult,
+) {
+    let row = match sqlx::query!(
+        r#"
+SELECT
+    created,
+    last_ping,
+    state AS "state?: CheckState",
+    message
+FROM
+    heartbeat
+WHERE
+    status_id = ?
+"#,
+        status_id
+    )
+    .fetch_one(db)
+    .await
+    {
+        Ok(row) => row,
+        Err(err) => return result.fail(ErrorKind::Unknown, &err),
+    };
+
+    // before the first ping the monitor gets a full period from its creation
+    let since = row.last_ping.unwrap_or(row.created);
+    if is_late(heartbeat, since, chrono::Utc::now().naive_utc()) {
+        result.state = CheckState::Down;
+        result.error_kind = Some(ErrorKind::MissedHeartbeat);
+        result.error_message = Some(match row.last_ping {
+            Some(last_ping) => format!("last ping at {last_ping}"),
+            None => "no ping received".to_owned(),
+        });
+        return;
+    }
+
+    result.state = row.state.unwrap_or(CheckState::Up);
+    if result.state != CheckState::Up {
+        result.error_kind = Some(ErrorKind::JobFailed);
+        result.error_message = row.message;
+    }
+}
+
+fn is_late(heartbeat: &Heartbeat, since: NaiveDateTime, now: NaiveDateTime) -> bool {
+    now - since
+        > chrono::TimeDelta::from_std(heartbeat.period + heartbeat.grace)
+            .unwrap_or(chrono::TimeDelta::MAX)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Ping {
+    #[serde(default)]
+    status: Option<CheckState>,
+    #[serde(default)]
+    msg: Option<String>,
+}
+
+/// Records a ping, e.g. `GET /heartbeat/{token}?status=down&msg=disk%20full`
+pub async fn ping_heartbeat(
+    State(db): State<SqlitePool>,
+    Path(token): Path<String>,
+    Query(ping): Query<Ping>,
+) -> StatusCode {
+    let state = ping.status.unwrap_or(CheckState::Up);
+    let res = sqlx::query!(
+        r#"
+UPDATE
+    heartbeat
+SET
+    last_ping = CURRENT_TIMESTAMP,
+    state = ?,
+    message = ?
+WHERE
+    token = ?
+"#,
+        state,
+        ping.msg,
+        token
+    )
+    .execute(&db)
+    .await;
+    match res {
+        Ok(res) if res.rows_affected() == 0 => StatusCode::NOT_FOUND,
+        Ok(_) => StatusCode::OK,
+        Err(err) => {
+            error!(?err, "Failed to record heartbeat");
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::status_check::tests::memory_db;
+
+    #[test]
+    fn test_is_late() {
+        let heartbeat = Heartbeat {
+            period: Duration::from_secs(60 * 60),
+            grace: Duration::from_secs(5 * 60),
+        };
+        let since = chrono::NaiveDate::from_ymd_opt(2026, 10, 18)
+            .unwrap()
+            .and_hms_opt(10, 0, 0)
+            .unwrap();
+        let after = |minutes| since + chrono::TimeDelta::minutes(minutes);
+        assert!(!is_late(&heartbeat, since, after(30)));
+        assert!(!is_late(&heartbeat, since, after(64)));
+        assert!(!is_late(&heartbeat, since, after(65)));
+        assert!(is_late(&heartbeat, since, after(66)));
+
+        let forever = Heartbeat {
+            period: Duration::MAX,
+            grace: Duration::ZERO,
+        };
+        assert!(!is_late(&forever, since, after(60 * 24 * 365)));
+    }
+
+    #[tokio::test]
+    async fn test_ping_heartbeat() {
+        let db = memory_db().await;
+        assert_eq!(ping_path(&db, "a").await.unwrap(), None);
+        let mut conn = db.acquire().await.unwrap();
+        let token = create_token(&mut conn, 1).await.unwrap().unwrap();
+        assert_eq!(create_token(&mut conn, 1).await.unwrap(), None);
+        drop(conn);
+        assert_eq!(
+            ping_path(&db, "a").await.unwrap(),
+            Some(format!("/heartbeat/{token}"))
+        );
+
+        let ping = |token: &str, status| {
+            ping_heartbeat(
+                State(db.clone()),
+                Path(token.to_owned()),
+                Query(Ping {
+                    status,
+                    msg: Some("disk full".to_owned()),
+                }),
+            )
+        };
+        assert_eq!(ping("unknown", None).await, StatusCode::NOT_FOUND);
+        assert_eq!(ping(&token, Some(CheckState::Down)).await, StatusCode::OK);
+        let (state, message): (String, Option<String>) =
+            sqlx::query_as("SELECT state, message FROM heartbeat WHERE status_id = 1")
+                .fetch_one(&db)
+                .await
+                .unwrap();
+        assert_eq!(state, "down");
+        assert_eq!(message.as_deref(), Some("disk full"));
+    }
+}
@@ -0,0 +1,269 @@
+use std::error::Error as _;
+
+use anyhow::Context;
+use reqwest::{
+    header::{HeaderMap, HeaderName, HeaderValue, CONTENT_TYPE, HOST, USER_AGENT},
+    Method,
+};
+use tokio::time::Instant;
+use tracing::debug;
+
+use super::{classify_io_error, CertificateInfo, CheckResult, Monitor};
+use crate::app::{CheckState, Entry, ErrorKind};
+
+/// Default limit of response body bytes read by a check
+const DEFAULT_MAX_BODY_BYTES: usize = 1024 * 1024;
+
+/// The request sent by an HTTP check, built once from the entry's config
+#[derive(Debug, Clone)]
+pub struct HttpRequest {
+    pub method: Method,
+    pub headers: HeaderMap,
+    pub body: Option<Vec<u8>>,
+}
+
+impl HttpRequest {
+    pub fn from_entry(entry: &Entry) -> anyhow::Result<Self> {
+        let method = match entry.method.as_deref() {
+            Some(method) => Method::from_bytes(method.to_uppercase().as_bytes())
+                .with_context(|| format!("Invalid method {method:?}"))?,
+            None => Method::GET,
+        };
+
+        let mut headers = HeaderMap::new();
+        for (name, value) in &entry.headers {
+            let name = HeaderName::from_bytes(name.as_bytes())
+                .with_context(|| format!("Invalid header name {name:?}"))?;
+            let value = HeaderValue::from_str(value)
+                .with_context(|| format!("Invalid value for header {name}"))?;
+            headers.append(name, value);
+        }
+        if let Some(user_agent) = &entry.user_agent {
+            let value = HeaderValue::from_str(user_agent).context("Invalid user_agent")?;
+            headers.insert(USER_AGENT, value);
+        }
+        if let Some(host) = &entry.host {
+            let value = HeaderValue::from_str(host).context("Invalid host")?;
+            headers.insert(HOST, value);
+        }
+
+        let body = match (&entry.body, &entry.json_body) {
+            (Some(_), Some(_)) => anyhow::bail!("Only one of body and json_body can be set"),
+            (Some(body), None) => Some(body.clone().into_bytes()),
+            (None, Some(json)) => {
+                if !headers.contains_key(CONTENT_TYPE) {
+                    headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+                }
+                Some(serde_json::to_vec(json).context("Failed to serialize json_body")?)
+            }
+            (None, None) => None,
+        };
+
+        Ok(Self {
+            method,
+            headers,
+            body,
+        })
+    }
+
+    pub fn build(&self, client: &reqwest::Client, url: &str) -> reqwest::RequestBuilder {
+        let req = client
+            .request(self.method.clone(), url)
+            .headers(self.headers.clone());
+        match &self.body {
+            Some(body) => req.body(body.clone()),
+            None => req,
+        }
+    }
+}
+
+pub(super) async fn check(
+    client: &reqwest::Client,
+    monitor: &Monitor,
+    request: &HttpRequest,
+    result: &mut CheckResult,
+) {
+    let started = Instant::now();
+    let resp = match request
+        .build(client, &result.url)
+        .timeout(monitor.timeout)
+        .send()
+        .await
+    {
+        Ok(resp) => resp,
+        Err(err) => return result.fail(classify_error(&err), &err),
+    };
+    let ttfb = started.elapsed();
+    let status = resp.status().as_u16();
+    result.status_code = Some(status as i64);
+    result.certificate = resp
+        .extensions()
+        .get::<reqwest::tls::TlsInfo>()
+        .and_then(|info| info.peer_certificate())
+        .and_then(|der| match CertificateInfo::from_der(der) {
+            Ok(cert) => Some(cert),
+            Err(err) => {
+                debug!(?err, url = result.url, "Failed to read peer certificate");
+                None
+            }
+        });
+    let max_body_bytes = monitor
+        .entry
+        .max_body_bytes
+        .unwrap_or(DEFAULT_MAX_BODY_BYTES);
+    let body = match read_body(resp, max_body_bytes).await {
+        Ok(body) => body,
+        Err(err) => return result.fail(classify_error(&err), &err),
+    };
+    result.latency_ms = Some(started.elapsed().as_millis() as i64);
+    result.ttfb_ms = Some(ttfb.as_millis() as i64);
+    result.state = monitor.entry.status_state(status);
+
+    if !monitor.assertions.is_empty() {
+        result.fail_assertions(monitor.assertions.check(&body));
+    }
+
+    if let Some(cert) = &result.certificate {
+        let now = chrono::Utc::now().naive_utc();
+        if let Some(message) =
+            expiring_soon(result.state, cert.not_after, now, monitor.cert_expiry_days)
+        {
+            result.state = CheckState::Degraded;
+            result.error_kind = Some(ErrorKind::CertificateExpiring);
+            result.error_message = Some(message);
+        }
+    }
+}
+
+/// Why an up check is degraded by its certificate expiring within `expiry_days`, None if it is
+/// not
+fn expiring_soon(
+    state: CheckState,
+    not_after: chrono::NaiveDateTime,
+    now: chrono::NaiveDateTime,
+    expiry_days: u32,
+) -> Option<String> {
+    let remaining = not_after - now;
+    (state == CheckState::Up && remaining < chrono::TimeDelta::days(expiry_days.into())).then(
+        || {
+            format!(
+                "certificate expires in {} days at {not_after}",
+                remaining.num_days()
+            )
+        },
+    )
+}
+
+/// Reads at most `limit` bytes of the response body
+async fn read_body(mut resp: reqwest::Response, limit: usize) -> reqwest::Result<Vec<u8>> {
+    let mut body = Vec::new();
+    while body.len() < limit {
+        match resp.chunk().await? {
+            Some(chunk) => body.extend_from_slice(&chunk),
+            None => break,
+        }
+    }
+    body.truncate(limit);
+    Ok(body)
+}
+
+fn classify_error(err: &reqwest::Error) -> ErrorKind {
+    if err.is_timeout() {
+        return ErrorKind::Timeout;
+    }
+    if err.is_redirect() {
+        return ErrorKind::TooManyRedirects;
+    }
+    if err.is_body() || err.is_decode() {
+        return ErrorKind::Body;
+    }
+    // the underlying connector errors are not exposed by reqwest, so look at the source chain
+    let mut source = err.source();
+    while let Some(e) = source {
+        if let Some(kind) = e
+            .downcast_ref::<std::io::Error>()
+            .and_then(classify_io_error)
+        {
+            return kind;
+        }
+        let msg = e.to_string().to_lowercase();
+        if msg.contains("dns error") || msg.contains("failed to lookup address") {
+            return ErrorKind::Dns;
+        }
+        if ["tls", "ssl", "certificate", "handshake"]
+            .iter()
+            .any(|needle| msg.contains(needle))
+        {
+            return ErrorKind::Tls;
+        }
+        source = e.source();
+    }
+    if err.is_connect() {
+        ErrorKind::Connect
+    } else {
+        ErrorKind::Unknown
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_from_entry() {
+        let entry: Entry = toml::from_str(
+            r#"
+name = "api"
+public_url = "https://example.com"
+polling_url = "http://10.0.0.1/api"
+method = "post"
+user_agent = "uptime-check"
+host = "example.com"
+headers = { Authorization = "Bearer token" }
+json_body = { ping = true }
+"#,
+        )
+        .unwrap();
+        let req = HttpRequest::from_entry(&entry).unwrap();
+
+        assert_eq!(req.method, Method::POST);
+        assert_eq!(req.headers[USER_AGENT], "uptime-check");
+        assert_eq!(req.headers[HOST], "example.com");
+        assert_eq!(req.headers["authorization"], "Bearer token");
+        assert_eq!(req.headers[CONTENT_TYPE], "application/json");
+        assert_eq!(req.body.as_deref(), Some(br#"{"ping":true}"#.as_slice()));
+    }
+
+    #[test]
+    fn test_expiring_soon() {
+        let not_after = chrono::NaiveDate::from_ymd_opt(2027, 1, 1)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap();
+        let before = |days| not_after - chrono::TimeDelta::days(days);
+
+        assert_eq!(
+            expiring_soon(CheckState::Up, not_after, before(10), 14).as_deref(),
+            Some("certificate expires in 10 days at 2027-01-01 00:00:00")
+        );
+        assert_eq!(
+            expiring_soon(CheckState::Up, not_after, before(14), 14),
+            None
+        );
+        assert_eq!(
+            expiring_soon(CheckState::Up, not_after, before(30), 14),
+            None
+        );
+        // expired certificates fail the TLS handshake, this only runs when the check passed
+        assert!(expiring_soon(CheckState::Up, not_after, before(-1), 14).is_some());
+        assert_eq!(
+            expiring_soon(CheckState::Degraded, not_after, before(10), 14),
+            None
+        );
+        assert_eq!(
+            expiring_soon(CheckState::Down, not_after, before(10), 14),
+            None
+        );
+        assert_eq!(expiring_soon(CheckState::Up, not_after, before(1), 0), None);
+    }
+}
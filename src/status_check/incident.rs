@@ -0,0 +1,117 @@
+use anyhow::Context;
+use chrono::NaiveDateTime;
+use sqlx::SqliteConnection;
+
+use super::CheckResult;
+use crate::app::CheckState;
+
+/// Opens an incident when a check goes down and closes it when a check is up or degraded again
+pub(super) async fn record(
+    conn: &mut SqliteConnection,
+    result: &CheckResult,
+    checked: NaiveDateTime,
+) -> anyhow::Result<()> {
+    if result.state == CheckState::Down {
+        // the first failure is kept as the reason of an ongoing incident
+        sqlx::query!(
+            r#"
+INSERT INTO
+    incident (status_id, started, error_kind, error_message)
+SELECT
+    $1,
+    $2,
+    $3,
+    $4
+WHERE
+    NOT EXISTS (
+        SELECT
+            1
+        FROM
+            incident
+        WHERE
+            status_id = $1
+            AND ended IS NULL
+    )
+"#,
+            result.status_id,
+            checked,
+            result.error_kind,
+            result.error_message
+        )
+        .execute(&mut *conn)
+        .await
+        .context("Failed to open incident")?;
+    } else {
+        sqlx::query!(
+            r#"
+UPDATE
+    incident
+SET
+    ended = ?
+WHERE
+    status_id = ?
+    AND ended IS NULL
+"#,
+            checked,
+            result.status_id
+        )
+        .execute(&mut *conn)
+        .await
+        .context("Failed to close incident")?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        app::ErrorKind,
+        status_check::tests::{memory_db, test_monitor},
+    };
+
+    #[tokio::test]
+    async fn test_record() {
+        let db = memory_db().await;
+        let mut conn = db.acquire().await.unwrap();
+        let monitor = test_monitor("tcp://127.0.0.1:1");
+        let at = |minute| {
+            chrono::NaiveDate::from_ymd_opt(2026, 10, 18)
+                .unwrap()
+                .and_hms_opt(10, minute, 0)
+                .unwrap()
+        };
+        let states = [
+            (0, CheckState::Up),
+            (1, CheckState::Down),
+            (2, CheckState::Down),
+            (3, CheckState::Degraded),
+            (4, CheckState::Up),
+            (5, CheckState::Down),
+            (6, CheckState::Down),
+        ];
+        for (minute, state) in states {
+            let mut result = CheckResult::new(&monitor);
+            result.state = state;
+            if state == CheckState::Down {
+                result.error_kind = Some(ErrorKind::Timeout);
+                result.error_message = Some(format!("failed at {minute}"));
+            }
+            record(&mut conn, &result, at(minute)).await.unwrap();
+        }
+
+        let incidents: Vec<(NaiveDateTime, Option<NaiveDateTime>, String)> = sqlx::query_as(
+            "SELECT started, ended, error_message FROM incident WHERE status_id = 1 ORDER BY id",
+        )
+        .fetch_all(&mut *conn)
+        .await
+        .unwrap();
+        assert_eq!(
+            incidents,
+            vec![
+                (at(1), Some(at(3)), "failed at 1".to_owned()),
+                (at(5), None, "failed at 5".to_owned()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,820 @@
+use std::{
+    collections::{HashMap, HashSet},
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    time::Duration,
+};
+
+use anyhow::Context;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde_derive::Serialize;
+use sqlx::{SqliteConnection, SqlitePool};
+use tokio::sync::watch;
+use tracing::{debug, error, warn};
+
+use super::{CheckResult, Monitor};
+use crate::app::{CheckState, ErrorKind, NotifierConfig, NotifierKind};
+
+mod chat;
+mod policy;
+mod smtp;
+mod webhook;
+
+use policy::AlertState;
+pub use policy::Policy;
+
+/// How often state changes are dispatched and pending deliveries are retried
+const DELIVERY_INTERVAL: Duration = Duration::from_secs(5);
+const SEND_TIMEOUT: Duration = Duration::from_secs(10);
+/// A delivery is given up after this many failed attempts
+const MAX_ATTEMPTS: i64 = 12;
+const FIRST_RETRY_DELAY: Duration = Duration::from_secs(10);
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(60 * 60);
+/// A failing delivery holds back the later ones of its notifier for at most this long
+const MAX_HOLD_BACK: Duration = Duration::from_secs(10 * 60);
+
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// Sends state changes somewhere, failed sends are retried with a backoff
+pub trait Notifier: Send + Sync {
+    fn send<'a>(&'a self, change: &'a StateChange) -> BoxFuture<'a, anyhow::Result<()>>;
+}
+
+/// Configured notifiers by name
+pub type Notifiers = HashMap<String, Arc<dyn Notifier>>;
+
+pub fn build_notifiers(configs: &[NotifierConfig]) -> anyhow::Result<Notifiers> {
+    build(configs, true)
+}
+
+/// Checks the notifier settings without reading their secrets, which may only exist where the
+/// server runs
+pub(super) fn check_notifiers(configs: &[NotifierConfig]) -> anyhow::Result<()> {
+    build(configs, false).map(|_| ())
+}
+
+fn build(configs: &[NotifierConfig], read_secrets: bool) -> anyhow::Result<Notifiers> {
+    let client = reqwest::Client::builder()
+        .user_agent(concat!("uptime/", env!("CARGO_PKG_VERSION")))
+        .timeout(SEND_TIMEOUT)
+        .build()
+        .context("Failed to build http client")?;
+    let mut notifiers = Notifiers::new();
+    for config in configs {
+        let notifier: Arc<dyn Notifier> = match &config.kind {
+            NotifierKind::Webhook { url, headers } => Arc::new(
+                webhook::Webhook::new(client.clone(), url.clone(), headers)
+                    .with_context(|| format!("Invalid webhook notifier {}", config.name))?,
+            ),
+            NotifierKind::Smtp {
+                host,
+                port,
+                tls,
+                username,
+                password_env,
+                password_file,
+                from,
+                to,
+            } => Arc::new(
+                smtp::Smtp::new(smtp::SmtpSettings {
+                    host,
+                    port: *port,
+                    tls: *tls,
+                    username: username.as_deref(),
+                    password_env: password_env.as_deref(),
+                    password_file: password_file.as_deref(),
+                    read_password: read_secrets,
+                    from,
+                    to,
+                })
+                .with_context(|| format!("Invalid smtp notifier {}", config.name))?,
+            ),
+            NotifierKind::Slack { url } => Arc::new(
+                chat::Chat::new(client.clone(), url.clone(), chat::ChatFormat::Slack)
+                    .with_context(|| format!("Invalid slack notifier {}", config.name))?,
+            ),
+            NotifierKind::Discord { url } => Arc::new(
+                chat::Chat::new(client.clone(), url.clone(), chat::ChatFormat::Discord)
+                    .with_context(|| format!("Invalid discord notifier {}", config.name))?,
+            ),
+            NotifierKind::Mattermost { url, channel } => Arc::new(
+                chat::Chat::new(
+                    client.clone(),
+                    url.clone(),
+                    chat::ChatFormat::Mattermost {
+                        channel: channel.clone(),
+                    },
+                )
+                .with_context(|| format!("Invalid mattermost notifier {}", config.name))?,
+            ),
+            NotifierKind::Teams { url } => Arc::new(
+                chat::Chat::new(client.clone(), url.clone(), chat::ChatFormat::Teams)
+                    .with_context(|| format!("Invalid teams notifier {}", config.name))?,
+            ),
+        };
+        anyhow::ensure!(
+            notifiers.insert(config.name.clone(), notifier).is_none(),
+            "Duplicate notifier name {:?}",
+            config.name
+        );
+    }
+    Ok(notifiers)
+}
+
+/// Why a notification is sent
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(rename_all = "snake_case")]
+pub enum NotificationKind {
+    /// The state changed for as many checks as the policy asks for
+    Change,
+    /// The monitor is still down
+    Reminder,
+    /// The state changed too often, further changes are not notified until it settles
+    Flapping,
+    /// The monitor settled in `new_state`
+    FlappingStopped,
+}
+
+/// Payload of a notification
+#[derive(Debug, Clone, Serialize)]
+pub struct StateChange {
+    /// Same for every attempt of a delivery, so receivers can drop duplicates
+    pub delivery_id: i64,
+    pub kind: NotificationKind,
+    pub monitor: MonitorInfo,
+    /// Last notified state, None if nothing was notified for the monitor yet
+    pub old_state: Option<CheckState>,
+    pub new_state: CheckState,
+    pub error_kind: Option<ErrorKind>,
+    /// Failure of the check that changed the state
+    pub reason: Option<String>,
+    pub checked_at: DateTime<Utc>,
+    /// Last check in another state than `new_state`
+    pub previous_check_at: Option<DateTime<Utc>>,
+}
+
+impl StateChange {
+    /// Kind and message of the failure
+    pub fn failure(&self) -> Option<String> {
+        match (self.error_kind, &self.reason) {
+            (Some(kind), Some(reason)) => Some(format!("{kind}: {reason}")),
+            (Some(kind), None) => Some(kind.to_string()),
+            (None, reason) => reason.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MonitorInfo {
+    pub id: i64,
+    pub key: String,
+    pub name: String,
+    pub public_url: String,
+    /// Page of the monitor on the dashboard, if `dashboard_url` is configured
+    pub details_url: Option<url::Url>,
+}
+
+fn details_url(dashboard_url: &url::Url, id: i64) -> Option<url::Url> {
+    let mut base = dashboard_url.clone();
+    if !base.path().ends_with('/') {
+        base.set_path(&format!("{}/", base.path()));
+    }
+    base.join(&format!("site/{id}")).ok()
+}
+
+/// Queues a notification if the notification policy of the monitor asks for one
+///
+/// The policy looks at the stored history, so `result` has to be inserted already.
+pub(super) async fn record_change(
+    conn: &mut SqliteConnection,
+    result: &CheckResult,
+    history_id: i64,
+    checked: NaiveDateTime,
+) -> anyhow::Result<()> {
+    // one more than needed to find the last check in another state
+    let limit = result.policy.checks_needed() + 1;
+    let recent = sqlx::query!(
+        r#"
+SELECT
+    state AS "state: CheckState",
+    created AS "created!: NaiveDateTime"
+FROM
+    status_history
+WHERE
+    status_id = ?
+    AND id <= ?
+ORDER BY
+    id DESC
+LIMIT
+    ?
+"#,
+        result.status_id,
+        history_id,
+        limit
+    )
+    .fetch_all(&mut *conn)
+    .await
+    .context("Failed to fetch recent checks")?;
+    let states = recent.iter().map(|r| r.state).collect::<Vec<_>>();
+
+    let mut alert = sqlx::query_as!(
+        AlertState,
+        r#"
+SELECT
+    notified_state AS "notified_state?: CheckState",
+    flapping,
+    last_notified
+FROM
+    alert_state
+WHERE
+    status_id = ?
+"#,
+        result.status_id
+    )
+    .fetch_optional(&mut *conn)
+    .await
+    .context("Failed to fetch alert state")?
+    .unwrap_or_default();
+    let Some(notification) = policy::evaluate(&result.policy, &mut alert, &states, checked) else {
+        return save_alert_state(conn, result.status_id, &alert).await;
+    };
+    save_alert_state(conn, result.status_id, &alert).await?;
+
+    let previous_check = recent
+        .iter()
+        .find(|r| r.state != result.state)
+        .map(|r| r.created);
+    sqlx::query!(
+        r#"
+INSERT INTO
+    state_change (
+        status_id,
+        kind,
+        old_state,
+        new_state,
+        error_kind,
+        error_message,
+        created,
+        previous_check
+    )
+VALUES
+    (?, ?, ?, ?, ?, ?, ?, ?)
+"#,
+        result.status_id,
+        notification.kind,
+        notification.old_state,
+        notification.new_state,
+        result.error_kind,
+        result.error_message,
+        checked,
+        previous_check
+    )
+    .execute(&mut *conn)
+    .await
+    .context("Failed to insert state change")?;
+    Ok(())
+}
+
+async fn save_alert_state(
+    conn: &mut SqliteConnection,
+    status_id: i64,
+    alert: &AlertState,
+) -> anyhow::Result<()> {
+    sqlx::query!(
+        r#"
+INSERT INTO
+    alert_state (status_id, notified_state, flapping, last_notified)
+VALUES
+    (?, ?, ?, ?)
+ON CONFLICT (status_id) DO UPDATE
+SET
+    notified_state = excluded.notified_state,
+    flapping = excluded.flapping,
+    last_notified = excluded.last_notified
+"#,
+        status_id,
+        alert.notified_state,
+        alert.flapping,
+        alert.last_notified
+    )
+    .execute(&mut *conn)
+    .await
+    .context("Failed to save alert state")?;
+    Ok(())
+}
+
+/// Queues a delivery of every new state change for the notifiers of its monitor and sends the
+/// due deliveries
+///
+/// Deliveries of a notifier are sent in order, a failed delivery holds back the later ones
+/// until it is delivered, given up or `MAX_HOLD_BACK` has passed.
+pub async fn deliver_notifications(
+    db: SqlitePool,
+    notifiers: watch::Receiver<Notifiers>,
+    dashboard_url: Option<url::Url>,
+    monitors: watch::Receiver<Vec<Monitor>>,
+) {
+    let mut interval = tokio::time::interval(DELIVERY_INTERVAL);
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    loop {
+        interval.tick().await;
+        let notifiers = notifiers.borrow().clone();
+        let routes = monitors
+            .borrow()
+            .iter()
+            .map(|m| (m.id, m.notify.clone()))
+            .collect::<HashMap<_, _>>();
+        if let Err(err) = dispatch(&db, &notifiers, &routes).await {
+            error!(?err, "Failed to dispatch state changes");
+        }
+        if let Err(err) = deliver(&db, &notifiers, dashboard_url.as_ref()).await {
+            error!(?err, "Failed to deliver notifications");
+        }
+    }
+}
+
+async fn dispatch(
+    db: &SqlitePool,
+    notifiers: &Notifiers,
+    routes: &HashMap<i64, Option<Arc<[String]>>>,
+) -> anyhow::Result<()> {
+    let mut all = notifiers.keys().cloned().collect::<Vec<_>>();
+    all.sort();
+    let mut tr = db
+        .begin_with("BEGIN IMMEDIATE")
+        .await
+        .context("Failed to begin transaction")?;
+    let pending = sqlx::query!(
+        r#"
+SELECT
+    id,
+    status_id
+FROM
+    state_change
+WHERE
+    NOT dispatched
+ORDER BY
+    id
+"#
+    )
+    .fetch_all(&mut *tr)
+    .await
+    .context("Failed to fetch new state changes")?;
+
+    for change in pending {
+        // archived entries are no longer routed, their changes go to every notifier
+        let names = match routes.get(&change.status_id) {
+            Some(Some(names)) => names,
+            _ => all.as_slice(),
+        };
+        for name in names {
+            sqlx::query!(
+                r#"
+INSERT INTO
+    notification_delivery (state_change_id, notifier)
+VALUES
+    (?, ?)
+"#,
+                change.id,
+                name
+            )
+            .execute(&mut *tr)
+            .await
+            .context("Failed to queue delivery")?;
+        }
+        sqlx::query!(
+            r#"
+UPDATE
+    state_change
+SET
+    dispatched = TRUE
+WHERE
+    id = ?
+"#,
+            change.id
+        )
+        .execute(&mut *tr)
+        .await
+        .context("Failed to mark state change as dispatched")?;
+    }
+    tr.commit().await.context("Failed to commit transaction")?;
+    Ok(())
+}
+
+async fn deliver(
+    db: &SqlitePool,
+    notifiers: &Notifiers,
+    dashboard_url: Option<&url::Url>,
+) -> anyhow::Result<()> {
+    let hold_back = format!("-{} seconds", MAX_HOLD_BACK.as_secs());
+    let pending = sqlx::query!(
+        r#"
+SELECT
+    d.id,
+    d.notifier,
+    d.attempts,
+    d.next_attempt <= CURRENT_TIMESTAMP AS "due!: bool",
+    d.created > datetime('now', ?) AS "holds_back!: bool",
+    sc.status_id,
+    sc.kind AS "kind: NotificationKind",
+    sc.old_state AS "old_state?: CheckState",
+    sc.new_state AS "new_state: CheckState",
+    sc.error_kind AS "error_kind?: ErrorKind",
+    sc.error_message,
+    sc.created,
+    sc.previous_check,
+    se.key,
+    se.name,
+    se.public_url
+FROM
+    notification_delivery AS d
+    INNER JOIN state_change AS sc ON sc.id = d.state_change_id
+    INNER JOIN status_entry AS se ON se.id = sc.status_id
+WHERE
+    d.delivered IS NULL
+    AND d.failed IS NULL
+ORDER BY
+    d.id
+"#,
+        hold_back
+    )
+    .fetch_all(db)
+    .await
+    .context("Failed to fetch pending deliveries")?;
+
+    let mut held_back = HashSet::new();
+    for d in pending {
+        if held_back.contains(&d.notifier) {
+            continue;
+        }
+        if !d.due {
+            if d.holds_back {
+                held_back.insert(d.notifier);
+            }
+            continue;
+        }
+        let Some(notifier) = notifiers.get(&d.notifier) else {
+            give_up(db, d.id, "notifier is no longer configured").await?;
+            continue;
+        };
+        let change = StateChange {
+            delivery_id: d.id,
+            kind: d.kind,
+            monitor: MonitorInfo {
+                id: d.status_id,
+                key: d.key,
+                name: d.name,
+                public_url: d.public_url,
+                details_url: dashboard_url.and_then(|u| details_url(u, d.status_id)),
+            },
+            old_state: d.old_state,
+            new_state: d.new_state,
+            error_kind: d.error_kind,
+            reason: d.error_message,
+            checked_at: d.created.and_utc(),
+            previous_check_at: d.previous_check.map(|t| t.and_utc()),
+        };
+        match notifier.send(&change).await {
+            Ok(()) => {
+                debug!(id = d.id, notifier = d.notifier, "Notification delivered");
+                sqlx::query!(
+                    r#"
+UPDATE
+    notification_delivery
+SET
+    attempts = attempts + 1,
+    delivered = CURRENT_TIMESTAMP,
+    last_error = NULL
+WHERE
+    id = ?
+"#,
+                    d.id
+                )
+                .execute(db)
+                .await
+                .context("Failed to mark delivery as delivered")?;
+            }
+            Err(err) => {
+                let attempts = d.attempts + 1;
+                let message = format!("{err:#}");
+                warn!(
+                    id = d.id,
+                    notifier = d.notifier,
+                    attempts,
+                    message,
+                    "Notification failed"
+                );
+                if attempts >= MAX_ATTEMPTS || is_permanent(&err) {
+                    give_up(db, d.id, &message).await?;
+                    continue;
+                }
+                let delay = format!("+{} seconds", retry_delay(attempts).as_secs());
+                sqlx::query!(
+                    r#"
+UPDATE
+    notification_delivery
+SET
+    attempts = ?,
+    next_attempt = datetime('now', ?),
+    last_error = ?
+WHERE
+    id = ?
+"#,
+                    attempts,
+                    delay,
+                    message,
+                    d.id
+                )
+                .execute(db)
+                .await
+                .context("Failed to schedule retry")?;
+                if d.holds_back {
+                    held_back.insert(d.notifier);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn give_up(db: &SqlitePool, id: i64, message: &str) -> anyhow::Result<()> {
+    error!(id, message, "Giving up on notification");
+    sqlx::query!(
+        r#"
+UPDATE
+    notification_delivery
+SET
+    attempts = attempts + 1,
+    failed = CURRENT_TIMESTAMP,
+    last_error = ?
+WHERE
+    id = ?
+"#,
+        message,
+        id
+    )
+    .execute(db)
+    .await
+    .context("Failed to give up delivery")?;
+    Ok(())
+}
+
+/// Whether a retry can not fix the error, e.g. a revoked webhook or a rejected payload
+fn is_permanent(err: &anyhow::Error) -> bool {
+    err.chain().any(|cause| {
+        if let Some(err) = cause.downcast_ref::<reqwest::Error>() {
+            return err.status().is_some_and(|status| {
+                status.is_client_error()
+                    && status != reqwest::StatusCode::REQUEST_TIMEOUT
+                    && status != reqwest::StatusCode::TOO_MANY_REQUESTS
+            });
+        }
+        cause
+            .downcast_ref::<lettre::transport::smtp::Error>()
+            .is_some_and(|err| err.is_permanent())
+    })
+}
+
+/// Exponential backoff after the given number of failed attempts
+fn retry_delay(attempts: i64) -> Duration {
+    let exponent = u32::try_from(attempts - 1).unwrap_or(0).min(16);
+    (FIRST_RETRY_DELAY * 2u32.pow(exponent)).min(MAX_RETRY_DELAY)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+    use crate::status_check::tests::memory_db;
+
+    /// Notification of the `api` monitor going down with a timeout
+    pub(super) fn test_change() -> StateChange {
+        StateChange {
+            delivery_id: 1,
+            kind: NotificationKind::Change,
+            monitor: MonitorInfo {
+                id: 3,
+                key: "api".to_owned(),
+                name: "api".to_owned(),
+                public_url: "https://example.com".to_owned(),
+                details_url: Some("https://status.example.com/site/3".parse().unwrap()),
+            },
+            old_state: Some(CheckState::Up),
+            new_state: CheckState::Down,
+            error_kind: Some(ErrorKind::Timeout),
+            reason: None,
+            checked_at: chrono::DateTime::UNIX_EPOCH,
+            previous_check_at: None,
+        }
+    }
+
+    /// Fails the given number of sends, then records the delivered ids
+    struct FakeNotifier {
+        failures: Mutex<usize>,
+        permanent: bool,
+        sent: Mutex<Vec<i64>>,
+    }
+
+    impl FakeNotifier {
+        fn new(failures: usize, permanent: bool) -> Arc<Self> {
+            Arc::new(Self {
+                failures: Mutex::new(failures),
+                permanent,
+                sent: Default::default(),
+            })
+        }
+    }
+
+    impl Notifier for FakeNotifier {
+        fn send<'a>(&'a self, change: &'a StateChange) -> BoxFuture<'a, anyhow::Result<()>> {
+            Box::pin(async move {
+                let mut failures = self.failures.lock().unwrap();
+                if *failures == 0 {
+                    self.sent.lock().unwrap().push(change.delivery_id);
+                    return Ok(());
+                }
+                *failures -= 1;
+                if self.permanent {
+                    let response = http::Response::builder().status(410).body("").unwrap();
+                    let err = reqwest::Response::from(response)
+                        .error_for_status()
+                        .unwrap_err();
+                    return Err(anyhow::Error::new(err).context("Failed to send webhook"));
+                }
+                anyhow::bail!("connection refused")
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_deliver_retries_in_order() {
+        let db = memory_db().await;
+        sqlx::query(
+            r#"
+INSERT INTO state_change (status_id, old_state, new_state, created) VALUES
+    (1, 'up', 'down', '2020-01-01 00:00:00'),
+    (1, 'down', 'up', '2020-01-01 00:01:00');
+"#,
+        )
+        .execute(&db)
+        .await
+        .unwrap();
+        let flaky = FakeNotifier::new(2, false);
+        let healthy = FakeNotifier::new(0, false);
+        let gone = FakeNotifier::new(usize::MAX, true);
+        let notifiers = Notifiers::from([
+            ("flaky".to_owned(), flaky.clone() as Arc<dyn Notifier>),
+            ("healthy".to_owned(), healthy.clone() as Arc<dyn Notifier>),
+            ("gone".to_owned(), gone.clone() as Arc<dyn Notifier>),
+        ]);
+
+        dispatch(&db, &notifiers, &HashMap::from([(1, None)]))
+            .await
+            .unwrap();
+        let queued = |notifier: &str| {
+            let db = db.clone();
+            let notifier = notifier.to_owned();
+            async move {
+                sqlx::query_scalar::<_, i64>(
+                    "SELECT id FROM notification_delivery WHERE notifier = ? ORDER BY state_change_id",
+                )
+                .bind(notifier)
+                .fetch_all(&db)
+                .await
+                .unwrap()
+            }
+        };
+        for name in ["flaky", "healthy", "gone"] {
+            assert_eq!(queued(name).await.len(), 2, "{name}");
+        }
+        let undispatched: i64 =
+            sqlx::query_scalar("SELECT count(*) FROM state_change WHERE NOT dispatched")
+                .fetch_one(&db)
+                .await
+                .unwrap();
+        assert_eq!(undispatched, 0);
+
+        let flaky_ids = queued("flaky").await;
+        // attempts and seconds until the next attempt of the first flaky delivery, `datetime` drops
+        // the fraction of the second
+        let first_flaky = flaky_ids[0];
+        let retry_in = || {
+            let db = db.clone();
+            async move {
+                sqlx::query_as::<_, (i64, i64)>(
+                    r#"
+SELECT
+    attempts,
+    unixepoch(next_attempt) - unixepoch('now')
+FROM
+    notification_delivery
+WHERE
+    id = ?
+"#,
+                )
+                .bind(first_flaky)
+                .fetch_one(&db)
+                .await
+                .unwrap()
+            }
+        };
+        // the time of the retry has come
+        let make_due = || {
+            let db = db.clone();
+            async move {
+                sqlx::query("UPDATE notification_delivery SET next_attempt = datetime('now')")
+                    .execute(&db)
+                    .await
+                    .unwrap();
+            }
+        };
+
+        deliver(&db, &notifiers, None).await.unwrap();
+        let healthy_ids = queued("healthy").await;
+        assert_eq!(*healthy.sent.lock().unwrap(), healthy_ids);
+        let (attempts, delay) = retry_in().await;
+        assert_eq!(attempts, 1);
+        assert!((9..=10).contains(&delay), "{delay}");
+        // the second flaky delivery waits for the first one
+        assert!(flaky.sent.lock().unwrap().is_empty());
+
+        make_due().await;
+        deliver(&db, &notifiers, None).await.unwrap();
+        let (attempts, delay) = retry_in().await;
+        assert_eq!(attempts, 2);
+        assert!((19..=20).contains(&delay), "{delay}");
+        assert!(flaky.sent.lock().unwrap().is_empty());
+
+        make_due().await;
+        deliver(&db, &notifiers, None).await.unwrap();
+        assert_eq!(*flaky.sent.lock().unwrap(), flaky_ids);
+        assert_eq!(healthy.sent.lock().unwrap().len(), 2);
+
+        let given_up = sqlx::query_as::<_, (i64, bool, bool, Option<String>)>(
+            r#"
+SELECT
+    attempts,
+    failed IS NOT NULL,
+    delivered IS NOT NULL,
+    last_error
+FROM
+    notification_delivery
+WHERE
+    notifier = 'gone'
+"#,
+        )
+        .fetch_all(&db)
+        .await
+        .unwrap();
+        assert_eq!(given_up.len(), 2);
+        for (attempts, failed, delivered, last_error) in given_up {
+            assert_eq!((attempts, failed, delivered), (1, true, false));
+            assert!(last_error.unwrap().contains("410"));
+        }
+        assert!(gone.sent.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_retry_delay() {
+        assert_eq!(retry_delay(1), Duration::from_secs(10));
+        assert_eq!(retry_delay(3), Duration::from_secs(40));
+        assert_eq!(retry_delay(MAX_ATTEMPTS), MAX_RETRY_DELAY);
+    }
+
+    #[test]
+    fn test_is_permanent() {
+        let error = |status: u16| {
+            let response = http::Response::builder().status(status).body("").unwrap();
+            let err = reqwest::Response::from(response)
+                .error_for_status()
+                .unwrap_err();
+            anyhow::Error::new(err).context("Failed to send webhook")
+        };
+        assert!(is_permanent(&error(404)));
+        assert!(is_permanent(&error(400)));
+        assert!(!is_permanent(&error(408)));
+        assert!(!is_permanent(&error(429)));
+        assert!(!is_permanent(&error(503)));
+        assert!(!is_permanent(&anyhow::anyhow!("connection refused")));
+    }
+
+    #[test]
+    fn test_details_url() {
+        let url = |base: &str| details_url(&base.parse().unwrap(), 3).unwrap().to_string();
+        assert_eq!(
+            url("https://status.example.com"),
+            "https://status.example.com/site/3"
+        );
+        assert_eq!(
+            url("https://example.com/uptime"),
+            "https://example.com/uptime/site/3"
+        );
+        assert_eq!(
+            url("https://example.com/uptime/"),
+            "https://example.com/uptime/site/3"
+        );
+    }
+}
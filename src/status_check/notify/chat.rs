@@ -0,0 +1,222 @@
+use anyhow::Context;
+use serde_json::{json, Value};
+
+use super::{BoxFuture, NotificationKind, Notifier, StateChange};
+use crate::app::CheckState;
+
+/// Message format of a chat platform's incoming webhook
+#[derive(Debug, Clone)]
+pub enum ChatFormat {
+    Slack,
+    Discord,
+    /// Slack compatible attachments, optionally posted to another channel
+    Mattermost {
+        channel: Option<String>,
+    },
+    Teams,
+}
+
+/// Posts every state change to a chat webhook in the platform's native format
+pub struct Chat {
+    client: reqwest::Client,
+    url: url::Url,
+    format: ChatFormat,
+}
+
+impl Chat {
+    pub fn new(client: reqwest::Client, url: url::Url, format: ChatFormat) -> anyhow::Result<Self> {
+        anyhow::ensure!(
+            url.scheme() == "https" || url.scheme() == "http",
+            "chat webhook url must be http or https, got {url}"
+        );
+        Ok(Self {
+            client,
+            url,
+            format,
+        })
+    }
+}
+
+impl Notifier for Chat {
+    fn send<'a>(&'a self, change: &'a StateChange) -> BoxFuture<'a, anyhow::Result<()>> {
+        Box::pin(async move {
+            let body = serde_json::to_vec(&render(&self.format, change))
+                .context("Failed to serialize message")?;
+            self.client
+                .post(self.url.clone())
+                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                .body(body)
+                .send()
+                .await
+                .context("Failed to send chat message")?
+                .error_for_status()
+                .context("Chat message was rejected")?;
+            Ok(())
+        })
+    }
+}
+
+fn headline(change: &StateChange) -> String {
+    let name = &change.monitor.name;
+    match change.kind {
+        NotificationKind::Reminder => return format!("{name} is still down"),
+        NotificationKind::Flapping => return format!("{name} is flapping"),
+        NotificationKind::FlappingStopped => return format!("{name} stopped flapping"),
+        NotificationKind::Change => {}
+    }
+    match (change.old_state, change.new_state) {
+        (_, CheckState::Down) => format!("{name} is down"),
+        (_, CheckState::Degraded) => format!("{name} is degraded"),
+        (Some(CheckState::Down), CheckState::Up) => format!("{name} recovered"),
+        (_, CheckState::Up) => format!("{name} is up"),
+    }
+}
+
+fn transition(change: &StateChange) -> String {
+    match change.old_state {
+        Some(old) => format!("{old} → {}", change.new_state),
+        None => change.new_state.to_string(),
+    }
+}
+
+/// Monitor page on the dashboard, or the public url if it can be opened in a browser
+fn link(change: &StateChange) -> Option<String> {
+    let monitor = &change.monitor;
+    monitor
+        .details_url
+        .as_ref()
+        .map(|u| u.to_string())
+        .or_else(|| {
+            let public = url::Url::parse(&monitor.public_url).ok()?;
+            matches!(public.scheme(), "http" | "https").then(|| public.to_string())
+        })
+}
+
+fn render(format: &ChatFormat, change: &StateChange) -> Value {
+    let (hex, discord, teams) = match change.new_state {
+        _ if change.kind == NotificationKind::Flapping => ("#daa038", 0xdaa038, "Warning"),
+        CheckState::Up => ("#2eb886", 0x2eb886, "Good"),
+        CheckState::Degraded => ("#daa038", 0xdaa038, "Warning"),
+        CheckState::Down => ("#d00000", 0xd00000, "Attention"),
+    };
+    let headline = headline(change);
+    let failure = change.failure();
+    let link = link(change);
+    let public_url = &change.monitor.public_url;
+
+    match format {
+        ChatFormat::Slack | ChatFormat::Mattermost { .. } => {
+            let mut attachment = json!({
+                "fallback": headline,
+                "color": hex,
+                "title": change.monitor.name,
+                "fields": [
+                    { "title": "State", "value": transition(change), "short": true },
+                    { "title": "Url", "value": public_url, "short": true },
+                ],
+                "ts": change.checked_at.timestamp(),
+            });
+            if let Some(link) = link {
+                attachment["title_link"] = json!(link);
+            }
+            if let Some(failure) = failure {
+                attachment["text"] = json!(failure);
+            }
+            let mut message = json!({ "text": headline, "attachments": [attachment] });
+            if let ChatFormat::Mattermost {
+                channel: Some(channel),
+            } = format
+            {
+                message["channel"] = json!(channel);
+            }
+            message
+        }
+        ChatFormat::Discord => {
+            let mut embed = json!({
+                "title": headline,
+                "color": discord,
+                "timestamp": change.checked_at.to_rfc3339(),
+                "fields": [
+                    { "name": "State", "value": transition(change), "inline": true },
+                    { "name": "Url", "value": public_url, "inline": true },
+                ],
+            });
+            if let Some(link) = link {
+                embed["url"] = json!(link);
+            }
+            if let Some(failure) = failure {
+                embed["description"] = json!(failure);
+            }
+            json!({ "embeds": [embed] })
+        }
+        ChatFormat::Teams => {
+            let mut facts = vec![json!({ "title": "State", "value": transition(change) })];
+            if let Some(failure) = failure {
+                facts.push(json!({ "title": "Reason", "value": failure }));
+            }
+            facts.push(json!({ "title": "Url", "value": public_url }));
+            let mut card = json!({
+                "$schema": "http://adaptivecards.io/schemas/adaptive-card.json",
+                "type": "AdaptiveCard",
+                "version": "1.4",
+                "body": [
+                    {
+                        "type": "TextBlock",
+                        "text": headline,
+                        "weight": "Bolder",
+                        "size": "Medium",
+                        "color": teams,
+                        "wrap": true,
+                    },
+                    { "type": "FactSet", "facts": facts },
+                ],
+            });
+            if let Some(link) = link {
+                card["actions"] = json!([
+                    { "type": "Action.OpenUrl", "title": "Open monitor", "url": link },
+                ]);
+            }
+            json!({
+                "type": "message",
+                "attachments": [
+                    { "contentType": "application/vnd.microsoft.card.adaptive", "content": card },
+                ],
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::status_check::notify::tests::test_change;
+
+    #[test]
+    fn test_render_formats() {
+        let change = test_change();
+        let link = "https://status.example.com/site/3";
+
+        let slack = render(&ChatFormat::Slack, &change);
+        assert_eq!(slack["text"], "api is down");
+        assert_eq!(slack["attachments"][0]["color"], "#d00000");
+        assert_eq!(slack["attachments"][0]["title_link"], link);
+        assert_eq!(slack["attachments"][0]["text"], "Timeout");
+
+        let mattermost = render(
+            &ChatFormat::Mattermost {
+                channel: Some("alerts".to_owned()),
+            },
+            &change,
+        );
+        assert_eq!(mattermost["channel"], "alerts");
+
+        let discord = render(&ChatFormat::Discord, &change);
+        assert_eq!(discord["embeds"][0]["color"], 0xd00000);
+        assert_eq!(discord["embeds"][0]["url"], link);
+
+        let teams = render(&ChatFormat::Teams, &change);
+        let card = &teams["attachments"][0]["content"];
+        assert_eq!(card["body"][0]["color"], "Attention");
+        assert_eq!(card["actions"][0]["url"], link);
+    }
+}
@@ -0,0 +1,235 @@
+use std::time::Duration;
+
+use chrono::{NaiveDateTime, TimeDelta};
+
+use super::NotificationKind;
+use crate::app::{CheckState, Config, Entry};
+
+const DEFAULT_FLAP_WINDOW: u32 = 20;
+const MAX_FLAP_WINDOW: u32 = 100;
+const MIN_REMIND_EVERY: Duration = Duration::from_secs(60);
+
+/// Notification policy of a monitor, the entry's `notify_policy` merged with the global one
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Policy {
+    pub alert_after: u32,
+    pub recover_after: u32,
+    pub remind_every: Option<Duration>,
+    pub flapping: Option<Flapping>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Flapping {
+    /// State changes within `window` checks that start flapping
+    pub threshold: u32,
+    pub window: u32,
+}
+
+impl Default for Policy {
+    fn default() -> Self {
+        Self {
+            alert_after: 1,
+            recover_after: 1,
+            remind_every: None,
+            flapping: None,
+        }
+    }
+}
+
+impl Policy {
+    pub fn new(entry: &Entry, config: &Config) -> anyhow::Result<Self> {
+        let global = &config.notify_policy;
+        let local = entry.notify_policy.as_ref();
+        let field =
+            |f: fn(&crate::app::NotifyPolicy) -> Option<u32>| local.and_then(f).or(f(global));
+
+        let policy = Self {
+            alert_after: field(|p| p.alert_after).unwrap_or(1),
+            recover_after: field(|p| p.recover_after).unwrap_or(1),
+            remind_every: local.and_then(|p| p.remind_every).or(global.remind_every),
+            flapping: field(|p| p.flap_threshold).map(|threshold| Flapping {
+                threshold,
+                window: field(|p| p.flap_window).unwrap_or(DEFAULT_FLAP_WINDOW),
+            }),
+        };
+        anyhow::ensure!(policy.alert_after > 0, "alert_after must be positive");
+        anyhow::ensure!(policy.recover_after > 0, "recover_after must be positive");
+        if let Some(remind_every) = policy.remind_every {
+            anyhow::ensure!(
+                remind_every >= MIN_REMIND_EVERY,
+                "remind_every must be at least a minute"
+            );
+        }
+        if let Some(flapping) = policy.flapping {
+            anyhow::ensure!(
+                flapping.window <= MAX_FLAP_WINDOW,
+                "flap_window must be at most {MAX_FLAP_WINDOW} checks"
+            );
+            anyhow::ensure!(
+                flapping.threshold >= 2 && flapping.threshold < flapping.window,
+                "flap_threshold must be at least 2 and less than flap_window"
+            );
+        }
+        Ok(policy)
+    }
+
+    /// Number of recent checks `evaluate` needs
+    pub fn checks_needed(&self) -> u32 {
+        let window = self.flapping.map(|f| f.window).unwrap_or(0);
+        self.alert_after.max(self.recover_after).max(window)
+    }
+}
+
+/// What the notifiers were last told about a monitor
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AlertState {
+    pub notified_state: Option<CheckState>,
+    pub flapping: bool,
+    pub last_notified: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Notification {
+    pub kind: NotificationKind,
+    pub old_state: Option<CheckState>,
+    pub new_state: CheckState,
+}
+
+/// Decides whether the latest check is notified, `recent` holds the states newest first
+///
+/// Flapping stops once the changes drop to half the threshold, so it does not toggle itself.
+pub fn evaluate(
+    policy: &Policy,
+    alert: &mut AlertState,
+    recent: &[CheckState],
+    now: NaiveDateTime,
+) -> Option<Notification> {
+    let &current = recent.first()?;
+    let old_state = alert.notified_state;
+    let notify = |alert: &mut AlertState, kind| {
+        alert.last_notified = Some(now);
+        Some(Notification {
+            kind,
+            old_state,
+            new_state: current,
+        })
+    };
+
+    if let Some(flapping) = policy.flapping {
+        let window = &recent[..recent.len().min(flapping.window as usize)];
+        let changes = window.windows(2).filter(|w| w[0] != w[1]).count() as u32;
+        if !alert.flapping && changes >= flapping.threshold {
+            alert.flapping = true;
+            return notify(alert, NotificationKind::Flapping);
+        }
+        if alert.flapping {
+            if changes * 2 > flapping.threshold {
+                return None;
+            }
+            alert.flapping = false;
+            alert.notified_state = Some(current);
+            return notify(alert, NotificationKind::FlappingStopped);
+        }
+    }
+
+    let is_down = current == CheckState::Down;
+    let needed = if is_down {
+        policy.alert_after
+    } else {
+        policy.recover_after
+    };
+    let streak = recent
+        .iter()
+        .take_while(|&&s| (s == CheckState::Down) == is_down)
+        .count();
+    if streak >= needed as usize && alert.notified_state != Some(current) {
+        alert.notified_state = Some(current);
+        // monitors that start up are not worth a notification
+        if old_state.is_none() && current == CheckState::Up {
+            return None;
+        }
+        return notify(alert, NotificationKind::Change);
+    }
+
+    if alert.notified_state == Some(CheckState::Down) && is_down {
+        let remind_every = TimeDelta::from_std(policy.remind_every?).ok()?;
+        if alert
+            .last_notified
+            .is_none_or(|last| now - last >= remind_every)
+        {
+            return notify(alert, NotificationKind::Reminder);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use CheckState::{Down, Up};
+
+    fn at(minute: u32) -> NaiveDateTime {
+        chrono::NaiveDate::from_ymd_opt(2026, 10, 18)
+            .unwrap()
+            .and_hms_opt(10, minute, 0)
+            .unwrap()
+    }
+
+    /// Feeds the checks oldest first and returns the kinds of the notifications
+    fn run(policy: &Policy, checks: &[CheckState]) -> Vec<(u32, NotificationKind, CheckState)> {
+        let mut alert = AlertState::default();
+        let mut notifications = Vec::new();
+        for i in 0..checks.len() {
+            let recent = checks[..=i].iter().rev().copied().collect::<Vec<_>>();
+            if let Some(n) = evaluate(policy, &mut alert, &recent, at(i as u32)) {
+                notifications.push((i as u32, n.kind, n.new_state));
+            }
+        }
+        notifications
+    }
+
+    #[test]
+    fn test_evaluate() {
+        use NotificationKind::{Change, FlappingStopped, Reminder};
+
+        let policy = Policy {
+            alert_after: 2,
+            recover_after: 3,
+            remind_every: Some(Duration::from_secs(5 * 60)),
+            flapping: None,
+        };
+        let checks = [
+            Up, Down, Up, Down, Down, Down, Up, Up, Up, Down, Down, Down, Down,
+        ];
+        assert_eq!(
+            run(&policy, &checks),
+            vec![(4, Change, Down), (8, Change, Up), (10, Change, Down)]
+        );
+        let mut down = vec![Down; 12];
+        down.insert(0, Up);
+        assert_eq!(
+            run(&policy, &down),
+            vec![(2, Change, Down), (7, Reminder, Down), (12, Reminder, Down)]
+        );
+
+        let policy = Policy {
+            flapping: Some(Flapping {
+                threshold: 4,
+                window: 6,
+            }),
+            ..Policy::default()
+        };
+        let checks = [Up, Down, Up, Down, Up, Up, Up, Up, Up, Up, Down];
+        assert_eq!(
+            run(&policy, &checks),
+            vec![
+                (1, Change, Down),
+                (2, Change, Up),
+                (3, Change, Down),
+                (4, NotificationKind::Flapping, Up),
+                (7, FlappingStopped, Up),
+                (10, Change, Down)
+            ]
+        );
+    }
+}
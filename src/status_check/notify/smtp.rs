@@ -0,0 +1,338 @@
+use std::path::Path;
+
+use anyhow::Context;
+use lettre::{
+    message::{Mailbox, MultiPart},
+    transport::smtp::authentication::Credentials,
+    AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor,
+};
+
+use super::{BoxFuture, NotificationKind, Notifier, StateChange, SEND_TIMEOUT};
+use crate::app::{CheckState, SmtpTls};
+
+/// Emails the down and recovery state changes
+pub struct Smtp {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+    to: Vec<Mailbox>,
+}
+
+/// Connection settings of the `smtp` notifier
+pub struct SmtpSettings<'a> {
+    pub host: &'a str,
+    pub port: Option<u16>,
+    pub tls: SmtpTls,
+    pub username: Option<&'a str>,
+    pub password_env: Option<&'a str>,
+    pub password_file: Option<&'a Path>,
+    /// False to only check that a password source is set
+    pub read_password: bool,
+    pub from: &'a str,
+    pub to: &'a [String],
+}
+
+impl Smtp {
+    pub fn new(settings: SmtpSettings) -> anyhow::Result<Self> {
+        let builder = match settings.tls {
+            SmtpTls::Starttls => {
+                AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(settings.host)
+                    .context("Failed to set up STARTTLS")?
+            }
+            SmtpTls::Implicit => AsyncSmtpTransport::<Tokio1Executor>::relay(settings.host)
+                .context("Failed to set up TLS")?,
+            SmtpTls::None => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(settings.host),
+        };
+        let mut builder = builder
+            .port(settings.port.unwrap_or(default_port(settings.tls)))
+            .timeout(Some(SEND_TIMEOUT));
+        if let Some(username) = settings.username {
+            let password = password(
+                settings.password_env,
+                settings.password_file,
+                settings.read_password,
+            )?;
+            builder = builder.credentials(Credentials::new(username.to_owned(), password));
+        }
+
+        anyhow::ensure!(!settings.to.is_empty(), "smtp notifier needs a recipient");
+        let parse = |address: &str| {
+            address
+                .parse::<Mailbox>()
+                .with_context(|| format!("Invalid email address {address:?}"))
+        };
+        Ok(Self {
+            transport: builder.build(),
+            from: parse(settings.from)?,
+            to: settings
+                .to
+                .iter()
+                .map(|to| parse(to))
+                .collect::<anyhow::Result<_>>()?,
+        })
+    }
+}
+
+fn default_port(tls: SmtpTls) -> u16 {
+    match tls {
+        SmtpTls::Starttls => 587,
+        SmtpTls::Implicit => 465,
+        SmtpTls::None => 25,
+    }
+}
+
+/// Reads the password, secrets are not written into the config
+fn password(env: Option<&str>, file: Option<&Path>, read: bool) -> anyhow::Result<String> {
+    match (env, file) {
+        (Some(_), None) | (None, Some(_)) if !read => Ok(String::new()),
+        (Some(env), None) => std::env::var(env).with_context(|| format!("Failed to read ${env}")),
+        (None, Some(file)) => std::fs::read_to_string(file)
+            .map(|p| p.trim_end_matches(['\r', '\n']).to_owned())
+            .with_context(|| format!("Failed to read {}", file.display())),
+        _ => anyhow::bail!("smtp username needs either password_env or password_file"),
+    }
+}
+
+impl Notifier for Smtp {
+    fn send<'a>(&'a self, change: &'a StateChange) -> BoxFuture<'a, anyhow::Result<()>> {
+        Box::pin(async move {
+            let Some(email) = render(change) else {
+                return Ok(());
+            };
+            let mut builder = Message::builder()
+                .from(self.from.clone())
+                .subject(email.subject);
+            for to in &self.to {
+                builder = builder.to(to.clone());
+            }
+            let message = builder
+                .multipart(MultiPart::alternative_plain_html(email.text, email.html))
+                .context("Failed to build email")?;
+            self.transport
+                .send(message)
+                .await
+                .context("Failed to send email")?;
+            Ok(())
+        })
+    }
+}
+
+struct Email {
+    subject: String,
+    text: String,
+    html: String,
+}
+
+/// Only changes to and from down are sent, None for the others
+fn render(change: &StateChange) -> Option<Email> {
+    let monitor = &change.monitor;
+    let (subject, summary) = if change.kind == NotificationKind::Flapping {
+        (
+            format!("[FLAPPING] {}", monitor.name),
+            format!(
+                "{} is flapping since {}, state changes are not notified until it settles",
+                monitor.name, change.checked_at
+            ),
+        )
+    } else if change.kind == NotificationKind::FlappingStopped {
+        (
+            format!("[STABLE] {}", monitor.name),
+            format!(
+                "{} stopped flapping and is {} since {}",
+                monitor.name,
+                change.new_state.to_string().to_lowercase(),
+                change.checked_at
+            ),
+        )
+    } else if change.kind == NotificationKind::Reminder {
+        (
+            format!("[STILL DOWN] {}", monitor.name),
+            format!("{} is still down at {}", monitor.name, change.checked_at),
+        )
+    } else if change.new_state == CheckState::Down {
+        (
+            format!("[DOWN] {}", monitor.name),
+            format!("{} is down since {}", monitor.name, change.checked_at),
+        )
+    } else if change.old_state == Some(CheckState::Down) {
+        (
+            format!("[RECOVERED] {}", monitor.name),
+            format!(
+                "{} is {} again since {}",
+                monitor.name,
+                change.new_state.to_string().to_lowercase(),
+                change.checked_at
+            ),
+        )
+    } else {
+        return None;
+    };
+
+    let mut text = format!("{summary}\n\nUrl: {}\n", monitor.public_url);
+    let mut html = format!(
+        "<p>{}</p>\n<p>Url: {}</p>\n",
+        escape_html(&summary),
+        escape_html(&monitor.public_url)
+    );
+    if let Some(reason) = change.failure() {
+        text.push_str(&format!("Reason: {reason}\n"));
+        html.push_str(&format!("<p>Reason: {}</p>\n", escape_html(&reason)));
+    }
+    if let Some(details) = &monitor.details_url {
+        text.push_str(&format!("Details: {details}\n"));
+        html.push_str(&format!(
+            "<p><a href=\"{0}\">{0}</a></p>\n",
+            escape_html(details.as_str())
+        ));
+    }
+    Some(Email {
+        subject,
+        text,
+        html,
+    })
+}
+
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::status_check::notify::tests::test_change;
+
+    #[test]
+    fn test_render() {
+        let mut change = test_change();
+        change.monitor.name = "<api>".to_owned();
+        let email = render(&change).unwrap();
+        assert_eq!(email.subject, "[DOWN] <api>");
+        assert!(email.text.contains("Reason: Timeout\n"), "{}", email.text);
+        assert!(email.html.contains("&lt;api&gt; is down"), "{}", email.html);
+        assert!(email
+            .html
+            .contains("<a href=\"https://status.example.com/site/3\">"));
+
+        change.old_state = Some(CheckState::Down);
+        change.new_state = CheckState::Degraded;
+        assert_eq!(render(&change).unwrap().subject, "[RECOVERED] <api>");
+        change.old_state = Some(CheckState::Up);
+        assert!(render(&change).is_none());
+        change.kind = NotificationKind::Flapping;
+        assert_eq!(render(&change).unwrap().subject, "[FLAPPING] <api>");
+    }
+
+    /// Minimal SMTP server that accepts a single email and returns the commands and the data
+    async fn smtp_sink(listener: tokio::net::TcpListener) -> (Vec<String>, String) {
+        use tokio::io::{AsyncBufReadExt as _, AsyncWriteExt as _, BufReader};
+
+        let (socket, _) = listener.accept().await.unwrap();
+        let (read, mut write) = socket.into_split();
+        let mut lines = BufReader::new(read).lines();
+        write.write_all(b"220 sink ready\r\n").await.unwrap();
+        let mut commands = Vec::new();
+        let mut data = String::new();
+        while let Some(line) = lines.next_line().await.unwrap() {
+            let reply: &[u8] = match line.split(' ').next().unwrap().to_uppercase().as_str() {
+                "EHLO" => b"250-sink\r\n250 AUTH PLAIN LOGIN\r\n",
+                "AUTH" => b"235 accepted\r\n",
+                "DATA" => {
+                    write.write_all(b"354 go ahead\r\n").await.unwrap();
+                    while let Some(line) = lines.next_line().await.unwrap() {
+                        if line == "." {
+                            break;
+                        }
+                        data.push_str(&line);
+                        data.push('\n');
+                    }
+                    b"250 queued\r\n"
+                }
+                "QUIT" => {
+                    commands.push(line);
+                    write.write_all(b"221 bye\r\n").await.unwrap();
+                    break;
+                }
+                _ => b"250 ok\r\n",
+            };
+            commands.push(line);
+            write.write_all(reply).await.unwrap();
+        }
+        (commands, data)
+    }
+
+    #[tokio::test]
+    async fn test_send_to_sink() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let sink = tokio::spawn(smtp_sink(listener));
+
+        std::env::set_var("UPTIME_TEST_SMTP_PASSWORD", "secret");
+        let to = [
+            "ops@example.com".to_owned(),
+            "Dev <dev@example.com>".to_owned(),
+        ];
+        let smtp = Smtp::new(SmtpSettings {
+            host: "127.0.0.1",
+            port: Some(port),
+            tls: SmtpTls::None,
+            username: Some("u"),
+            password_env: Some("UPTIME_TEST_SMTP_PASSWORD"),
+            password_file: None,
+            read_password: true,
+            from: "Uptime <uptime@example.com>",
+            to: &to,
+        })
+        .unwrap();
+        smtp.send(&test_change()).await.unwrap();
+        drop(smtp);
+        let (commands, data) = sink.await.unwrap();
+
+        // base64 of "\0u\0secret"
+        assert!(
+            commands.contains(&"AUTH PLAIN AHUAc2VjcmV0".to_owned()),
+            "{commands:?}"
+        );
+        let envelope = commands
+            .iter()
+            .filter(|c| c.starts_with("MAIL") || c.starts_with("RCPT"))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            envelope,
+            [
+                "MAIL FROM:<uptime@example.com>",
+                "RCPT TO:<ops@example.com>",
+                "RCPT TO:<dev@example.com>"
+            ]
+        );
+        assert!(data.contains("Subject: [DOWN] api\n"), "{data}");
+        assert!(data.contains("multipart/alternative"), "{data}");
+        assert!(data.contains("text/plain"), "{data}");
+        assert!(data.contains("Reason: Timeout\n"), "{data}");
+        assert!(data.contains("text/html"), "{data}");
+        assert!(data.contains("<p>api is down since"), "{data}");
+    }
+
+    #[test]
+    fn test_tls_modes() {
+        assert_eq!(default_port(SmtpTls::Starttls), 587);
+        assert_eq!(default_port(SmtpTls::Implicit), 465);
+        assert_eq!(default_port(SmtpTls::None), 25);
+        let to = ["ops@example.com".to_owned()];
+        for tls in [SmtpTls::Starttls, SmtpTls::Implicit, SmtpTls::None] {
+            let settings = SmtpSettings {
+                host: "smtp.example.com",
+                port: None,
+                tls,
+                username: None,
+                password_env: None,
+                password_file: None,
+                read_password: true,
+                from: "uptime@example.com",
+                to: &to,
+            };
+            assert!(Smtp::new(settings).is_ok());
+        }
+    }
+}
@@ -0,0 +1,61 @@
+use std::collections::BTreeMap;
+
+use anyhow::Context;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, CONTENT_TYPE};
+
+use super::{BoxFuture, Notifier, StateChange};
+
+/// POSTs the state change as JSON to `url`
+pub struct Webhook {
+    client: reqwest::Client,
+    url: url::Url,
+    headers: HeaderMap,
+}
+
+impl Webhook {
+    pub fn new(
+        client: reqwest::Client,
+        url: url::Url,
+        headers: &BTreeMap<String, String>,
+    ) -> anyhow::Result<Self> {
+        anyhow::ensure!(
+            matches!(url.scheme(), "http" | "https"),
+            "webhook url must be http or https, got {url}"
+        );
+        let headers = headers
+            .iter()
+            .map(|(name, value)| {
+                let name = HeaderName::try_from(name)
+                    .with_context(|| format!("Invalid header name {name:?}"))?;
+                let value = HeaderValue::try_from(value)
+                    .with_context(|| format!("Invalid value of header {name}"))?;
+                Ok((name, value))
+            })
+            .collect::<anyhow::Result<_>>()?;
+        Ok(Self {
+            client,
+            url,
+            headers,
+        })
+    }
+}
+
+impl Notifier for Webhook {
+    fn send<'a>(&'a self, change: &'a StateChange) -> BoxFuture<'a, anyhow::Result<()>> {
+        Box::pin(async move {
+            let body = serde_json::to_vec(change).context("Failed to serialize state change")?;
+            self.client
+                .post(self.url.clone())
+                .headers(self.headers.clone())
+                .header(CONTENT_TYPE, "application/json")
+                .header("X-Uptime-Delivery", change.delivery_id)
+                .body(body)
+                .send()
+                .await
+                .context("Failed to send webhook")?
+                .error_for_status()
+                .context("Webhook was rejected")?;
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,147 @@
+use std::{path::PathBuf, sync::Arc, time::Duration};
+
+use anyhow::Context;
+use sqlx::SqlitePool;
+use tokio::sync::{watch, Notify};
+use tracing::{error, info, warn};
+
+use super::{build_notifiers, init_statuses, validate_config, Monitor, Notifiers};
+use crate::app::Config;
+
+/// How often the config file is compared to the active config
+const CONFIG_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Reloads the config when the file changes or on SIGHUP and sends the new monitors and
+/// notifiers to `monitors` and `notifiers`
+///
+/// The file is polled instead of watched so the symlink swaps of mounted ConfigMaps are noticed.
+/// Invalid configs are logged and the previous config stays active.
+/// Concurrency limits, the retention and the dashboard url are only read at startup.
+pub async fn watch_config(
+    path: PathBuf,
+    mut contents: String,
+    db: SqlitePool,
+    monitors: watch::Sender<Vec<Monitor>>,
+    notifiers: watch::Sender<Notifiers>,
+) -> anyhow::Result<()> {
+    let hangup = Arc::new(Notify::new());
+    #[cfg(unix)]
+    {
+        let mut signal = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::hangup())
+            .context("Failed to install SIGHUP handler")?;
+        let hangup = hangup.clone();
+        tokio::spawn(async move {
+            while signal.recv().await.is_some() {
+                hangup.notify_one();
+            }
+        });
+    }
+
+    let mut interval = tokio::time::interval(CONFIG_POLL_INTERVAL);
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+    loop {
+        let forced = tokio::select! {
+            _ = interval.tick() => false,
+            _ = hangup.notified() => true,
+        };
+        let new_contents = match tokio::fs::read_to_string(&path).await {
+            Ok(c) => c,
+            Err(err) => {
+                warn!(?err, ?path, "Failed to read config file");
+                continue;
+            }
+        };
+        if !forced && new_contents == contents {
+            continue;
+        }
+        info!(?path, forced, "Reloading config");
+        if let Err(err) = apply(&db, &new_contents, &monitors, &notifiers).await {
+            error!(?err, "Invalid config, keeping the previous one");
+        }
+        // an invalid config is reported once, not on every poll
+        contents = new_contents;
+    }
+}
+
+/// Sends the monitors and notifiers of a valid config, nothing is sent for an invalid one
+async fn apply(
+    db: &SqlitePool,
+    contents: &str,
+    monitors: &watch::Sender<Vec<Monitor>>,
+    notifiers: &watch::Sender<Notifiers>,
+) -> anyhow::Result<()> {
+    let (new_monitors, new_notifiers) = reload(db, contents).await?;
+    info!(count = new_monitors.len(), "Config reloaded");
+    // the notifiers first, so the new routes never name a missing notifier
+    notifiers.send_replace(new_notifiers);
+    monitors.send_replace(new_monitors);
+    Ok(())
+}
+
+async fn reload(db: &SqlitePool, contents: &str) -> anyhow::Result<(Vec<Monitor>, Notifiers)> {
+    let problems = validate_config(contents);
+    if !problems.is_empty() {
+        let problems = problems.iter().map(|p| p.to_string()).collect::<Vec<_>>();
+        anyhow::bail!("{}", problems.join("; "));
+    }
+    let config: Config = toml::from_str(contents).context("Failed to parse config file")?;
+    let notifiers = build_notifiers(&config.notifiers).context("Failed to create notifiers")?;
+    let monitors = init_statuses(db, &config).await?;
+    Ok((monitors, notifiers))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::status_check::{tests::memory_db, Scheduler};
+
+    const CONFIG: &str = r#"notifiers = [{ name = "hook", type = "webhook", url = "http://127.0.0.1:1/" }]
+
+[[entries]]
+name = "a"
+public_url = "tcp://a:1"
+interval = "10s"
+"#;
+
+    #[tokio::test]
+    async fn test_apply() {
+        let db = memory_db().await;
+        let config: Config = toml::from_str(CONFIG).unwrap();
+        let initial = init_statuses(&db, &config).await.unwrap();
+        let (monitors, mut monitors_rx) = watch::channel(initial.clone());
+        let (notifiers, mut notifiers_rx) =
+            watch::channel(build_notifiers(&config.notifiers).unwrap());
+        let mut scheduler = Scheduler::new(initial);
+
+        let invalid = CONFIG
+            .replace("tcp://a:1", "ftp://a")
+            .replace("\"hook\"", "\"other\"");
+        assert!(apply(&db, &invalid, &monitors, &notifiers).await.is_err());
+        assert!(!monitors_rx.has_changed().unwrap());
+        assert!(!notifiers_rx.has_changed().unwrap());
+        let archived: i64 =
+            sqlx::query_scalar("SELECT count(*) FROM status_entry WHERE archived IS NOT NULL")
+                .fetch_one(&db)
+                .await
+                .unwrap();
+        assert_eq!(archived, 0);
+
+        let valid = CONFIG.replace("10s", "1m").replace("\"hook\"", "\"other\"");
+        apply(&db, &valid, &monitors, &notifiers).await.unwrap();
+        assert!(notifiers_rx.has_changed().unwrap());
+        let names = notifiers_rx
+            .borrow_and_update()
+            .keys()
+            .cloned()
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["other"]);
+        assert!(monitors_rx.has_changed().unwrap());
+        scheduler.update(monitors_rx.borrow_and_update().clone());
+        assert_eq!(scheduler.monitors.len(), 1);
+        assert_eq!(scheduler.monitors[0].monitor.id, 1);
+        assert_eq!(
+            scheduler.monitors[0].monitor.interval,
+            Duration::from_secs(60)
+        );
+    }
+}
@@ -0,0 +1,317 @@
+use std::{future::Future, time::Duration};
+
+use anyhow::Context;
+use sqlx::{sqlite::SqliteQueryResult, SqlitePool};
+use tokio::time::Instant;
+use tracing::{debug, error, info};
+
+/// How often expired checks are deleted and the WAL is checkpointed
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+/// Rows deleted per statement, so writes of the poller are not blocked for long
+const PRUNE_BATCH_SIZE: i64 = 1000;
+const VACUUM_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+/// The database is only vacuumed if this fraction of its pages is free
+const VACUUM_FREE_RATIO: f64 = 0.25;
+
+/// Deletes checks, notifications and incidents older than `retention` and keeps the database
+/// file from growing
+pub async fn prune_history(db: SqlitePool, retention: Option<Duration>) {
+    let mut interval = tokio::time::interval(PRUNE_INTERVAL);
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    let mut last_vacuum = Instant::now();
+    loop {
+        interval.tick().await;
+        if let Some(retention) = retention {
+            match delete_expired(&db, retention).await {
+                Ok(0) => {}
+                Ok(deleted) => info!(deleted, "Deleted expired checks"),
+                Err(err) => error!(?err, "Failed to delete expired checks"),
+            }
+            match delete_expired_events(&db, retention).await {
+                Ok(0) => {}
+                Ok(deleted) => info!(deleted, "Deleted expired notifications and incidents"),
+                Err(err) => error!(?err, "Failed to delete expired notifications and incidents"),
+            }
+        }
+        if let Err(err) = checkpoint(&db).await {
+            error!(?err, "Failed to checkpoint the WAL");
+        }
+        if last_vacuum.elapsed() >= VACUUM_INTERVAL {
+            last_vacuum = Instant::now();
+            if let Err(err) = vacuum(&db).await {
+                error!(?err, "Failed to vacuum the database");
+            }
+        }
+    }
+}
+
+fn cutoff(retention: Duration) -> anyhow::Result<chrono::NaiveDateTime> {
+    let retention = chrono::TimeDelta::from_std(retention).context("Retention is too long")?;
+    Ok(chrono::Utc::now().naive_utc() - retention)
+}
+
+/// Runs a delete of at most `PRUNE_BATCH_SIZE` rows until it deletes fewer
+async fn delete_in_batches<F, Fut>(mut delete: F) -> Result<u64, sqlx::Error>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<SqliteQueryResult, sqlx::Error>>,
+{
+    let mut total = 0;
+    loop {
+        let deleted = delete().await?.rows_affected();
+        total += deleted;
+        if deleted < PRUNE_BATCH_SIZE as u64 {
+            return Ok(total);
+        }
+        tokio::task::yield_now().await;
+    }
+}
+
+async fn delete_expired(db: &SqlitePool, retention: Duration) -> anyhow::Result<u64> {
+    let cutoff = cutoff(retention)?;
+    let total = delete_in_batches(|| {
+        sqlx::query!(
+            r#"
+DELETE FROM
+    status_history
+WHERE
+    id IN (
+        SELECT
+            id
+        FROM
+            status_history
+        WHERE
+            created < ?
+            -- checks are only deleted once they are in the rollups
+            AND id <= (
+                SELECT
+                    last_history_id
+                FROM
+                    rollup_state
+            )
+        LIMIT
+            ?
+    )
+"#,
+            cutoff,
+            PRUNE_BATCH_SIZE
+        )
+        .execute(db)
+    })
+    .await
+    .context("Failed to delete expired checks")?;
+    if total > 0 {
+        // the rollups of the pruned time must not be recomputed from the remaining checks
+        sqlx::query!(
+            r#"
+UPDATE
+    rollup_state
+SET
+    pruned_until = MAX(COALESCE(pruned_until, ?1), ?1)
+"#,
+            cutoff
+        )
+        .execute(db)
+        .await
+        .context("Failed to update rollup state")?;
+    }
+    Ok(total)
+}
+
+/// Deletes the state changes whose deliveries are all finished, the ended incidents and the
+/// assertion failures whose check is gone
+async fn delete_expired_events(db: &SqlitePool, retention: Duration) -> anyhow::Result<u64> {
+    let cutoff = cutoff(retention)?;
+    // deliveries are deleted with their state change
+    let state_changes = delete_in_batches(|| {
+        sqlx::query!(
+            r#"
+DELETE FROM
+    state_change
+WHERE
+    id IN (
+        SELECT
+            id
+        FROM
+            state_change AS sc
+        WHERE
+            created < ?
+            AND dispatched
+            AND NOT EXISTS (
+                SELECT
+                    1
+                FROM
+                    notification_delivery AS d
+                WHERE
+                    d.state_change_id = sc.id
+                    AND d.delivered IS NULL
+                    AND d.failed IS NULL
+            )
+        LIMIT
+            ?
+    )
+"#,
+            cutoff,
+            PRUNE_BATCH_SIZE
+        )
+        .execute(db)
+    })
+    .await
+    .context("Failed to delete expired state changes")?;
+    let incidents = delete_in_batches(|| {
+        sqlx::query!(
+            r#"
+DELETE FROM
+    incident
+WHERE
+    id IN (
+        SELECT
+            id
+        FROM
+            incident
+        WHERE
+            ended < ?
+        LIMIT
+            ?
+    )
+"#,
+            cutoff,
+            PRUNE_BATCH_SIZE
+        )
+        .execute(db)
+    })
+    .await
+    .context("Failed to delete expired incidents")?;
+    // left behind by deletes without foreign keys enforced
+    let assertion_failures = delete_in_batches(|| {
+        sqlx::query!(
+            r#"
+DELETE FROM
+    status_assertion_failure
+WHERE
+    id IN (
+        SELECT
+            id
+        FROM
+            status_assertion_failure AS f
+        WHERE
+            NOT EXISTS (
+                SELECT
+                    1
+                FROM
+                    status_history AS h
+                WHERE
+                    h.id = f.history_id
+            )
+        LIMIT
+            ?
+    )
+"#,
+            PRUNE_BATCH_SIZE
+        )
+        .execute(db)
+    })
+    .await
+    .context("Failed to delete orphaned assertion failures")?;
+    Ok(state_changes + incidents + assertion_failures)
+}
+
+async fn checkpoint(db: &SqlitePool) -> anyhow::Result<()> {
+    sqlx::query("PRAGMA wal_checkpoint(TRUNCATE)")
+        .execute(db)
+        .await
+        .context("Failed to run wal_checkpoint")?;
+    Ok(())
+}
+
+async fn vacuum(db: &SqlitePool) -> anyhow::Result<()> {
+    let pages: i64 = sqlx::query_scalar("PRAGMA page_count")
+        .fetch_one(db)
+        .await
+        .context("Failed to fetch page_count")?;
+    let free: i64 = sqlx::query_scalar("PRAGMA freelist_count")
+        .fetch_one(db)
+        .await
+        .context("Failed to fetch freelist_count")?;
+    if pages == 0 || (free as f64) < pages as f64 * VACUUM_FREE_RATIO {
+        debug!(pages, free, "Skipping vacuum");
+        return Ok(());
+    }
+    info!(pages, free, "Vacuuming the database");
+    sqlx::query("VACUUM")
+        .execute(db)
+        .await
+        .context("Failed to vacuum")?;
+    checkpoint(db).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::status_check::tests::memory_db;
+
+    #[tokio::test]
+    async fn test_delete_expired() {
+        let db = memory_db().await;
+        let retention = Duration::from_secs(24 * 60 * 60);
+        sqlx::query(
+            r#"
+INSERT INTO status_history (id, status_id, state, created) VALUES
+    (1, 1, 'down', '2020-01-01 00:00:00'),
+    (2, 1, 'up', '2020-01-01 00:01:00'),
+    (3, 1, 'up', '2020-01-01 00:02:00'),
+    (4, 1, 'up', datetime('now'));
+INSERT INTO status_assertion_failure (history_id, assertion, message) VALUES (1, 'a', 'b');
+UPDATE rollup_state SET last_history_id = 2;
+INSERT INTO state_change (id, status_id, new_state, created, dispatched) VALUES
+    (1, 1, 'down', '2020-01-01 00:00:00', TRUE),
+    (2, 1, 'up', '2020-01-01 00:01:00', TRUE),
+    (3, 1, 'down', datetime('now'), TRUE);
+INSERT INTO notification_delivery (state_change_id, notifier, delivered) VALUES
+    (1, 'a', '2020-01-01 00:00:00'),
+    (2, 'a', NULL),
+    (3, 'a', datetime('now'));
+INSERT INTO incident (status_id, started, ended) VALUES
+    (1, '2020-01-01 00:00:00', '2020-01-01 00:01:00'),
+    (1, datetime('now'), NULL);
+"#,
+        )
+        .execute(&db)
+        .await
+        .unwrap();
+
+        assert_eq!(delete_expired(&db, retention).await.unwrap(), 2);
+        // the third check is not in the rollups yet
+        let ids: Vec<i64> = sqlx::query_scalar("SELECT id FROM status_history ORDER BY id")
+            .fetch_all(&db)
+            .await
+            .unwrap();
+        assert_eq!(ids, vec![3, 4]);
+        let pruned_until: Option<String> =
+            sqlx::query_scalar("SELECT pruned_until FROM rollup_state")
+                .fetch_one(&db)
+                .await
+                .unwrap();
+        assert!(pruned_until.is_some());
+
+        // the state change with a pending delivery is kept
+        assert_eq!(delete_expired_events(&db, retention).await.unwrap(), 2);
+        let ids: Vec<i64> = sqlx::query_scalar("SELECT id FROM state_change ORDER BY id")
+            .fetch_all(&db)
+            .await
+            .unwrap();
+        assert_eq!(ids, vec![2, 3]);
+        let counts: (i64, i64, i64) = sqlx::query_as(
+            r#"
+SELECT
+    (SELECT COUNT(*) FROM notification_delivery),
+    (SELECT COUNT(*) FROM incident),
+    (SELECT COUNT(*) FROM status_assertion_failure)
+"#,
+        )
+        .fetch_one(&db)
+        .await
+        .unwrap();
+        assert_eq!(counts, (2, 1, 0));
+    }
+}
@@ -0,0 +1,604 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    time::Duration,
+};
+
+use anyhow::Context;
+use chrono::{NaiveDateTime, TimeDelta, Timelike};
+use sqlx::{Acquire, SqliteConnection, SqlitePool};
+use tokio::sync::watch;
+use tracing::{debug, error};
+
+use super::Monitor;
+use crate::app::{CheckState, RollupPeriod};
+
+/// How often the checks written since the last run are added to the rollups
+const ROLLUP_INTERVAL: Duration = Duration::from_secs(60);
+/// Checks added to the rollups per transaction, so the poller is not locked out for long
+const ROLLUP_BATCH_SIZE: i64 = 10_000;
+pub(super) const BUCKET_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+/// Longest time a check counts for when its monitor is no longer in the config
+const DEFAULT_MAX_GAP: TimeDelta = TimeDelta::hours(1);
+
+/// Keeps `status_rollup` up to date, so long term views do not need the raw checks
+///
+/// The state of a check lasts until the next check, but at most twice the interval of the
+/// monitor, so the time the server was stopped is not counted as up or down.
+pub async fn update_rollups(db: SqlitePool, monitors: watch::Receiver<Vec<Monitor>>) {
+    let mut interval = tokio::time::interval(ROLLUP_INTERVAL);
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    loop {
+        interval.tick().await;
+        let max_gaps = monitors
+            .borrow()
+            .iter()
+            .map(|m| {
+                let gap = TimeDelta::from_std(m.interval * 2).unwrap_or(DEFAULT_MAX_GAP);
+                (m.id, gap)
+            })
+            .collect::<HashMap<_, _>>();
+        if let Err(err) = rollup_once(&db, &max_gaps).await {
+            error!(?err, "Failed to update rollups");
+        }
+    }
+}
+
+fn bucket_start(period: RollupPeriod, time: NaiveDateTime) -> NaiveDateTime {
+    let hour = match period {
+        RollupPeriod::Hour => time.hour(),
+        RollupPeriod::Day => 0,
+    };
+    time.date().and_hms_opt(hour, 0, 0).expect("valid time")
+}
+
+fn bucket_len(period: RollupPeriod) -> TimeDelta {
+    match period {
+        RollupPeriod::Hour => TimeDelta::hours(1),
+        RollupPeriod::Day => TimeDelta::days(1),
+    }
+}
+
+/// Recomputes the buckets that received checks or check time since the last run
+async fn rollup_once(db: &SqlitePool, max_gaps: &HashMap<i64, TimeDelta>) -> anyhow::Result<()> {
+    while rollup_batch(db, max_gaps).await? {
+        tokio::task::yield_now().await;
+    }
+    Ok(())
+}
+
+/// Adds the next `ROLLUP_BATCH_SIZE` checks to the rollups, true if there are more
+async fn rollup_batch(db: &SqlitePool, max_gaps: &HashMap<i64, TimeDelta>) -> anyhow::Result<bool> {
+    // takes the write lock up front, a deferred transaction can not wait for the poller's writes
+    let mut tr = db
+        .begin_with("BEGIN IMMEDIATE")
+        .await
+        .context("Failed to begin transaction")?;
+    let conn = tr
+        .acquire()
+        .await
+        .context("Failed to acquire db connection")?;
+
+    let state = sqlx::query!(
+        r#"
+SELECT
+    last_history_id,
+    pruned_until AS "pruned_until: NaiveDateTime"
+FROM
+    rollup_state
+WHERE
+    id = 1
+"#
+    )
+    .fetch_one(&mut *conn)
+    .await
+    .context("Failed to fetch rollup state")?;
+
+    let last_history_id = sqlx::query!(
+        r#"
+SELECT
+    MAX(id) AS "max_id?: i64"
+FROM
+    status_history
+"#
+    )
+    .fetch_one(&mut *conn)
+    .await
+    .context("Failed to fetch last check")?
+    .max_id;
+    let Some(max_id) = last_history_id.filter(|&id| id > state.last_history_id) else {
+        return Ok(false);
+    };
+    let last_history_id = max_id.min(state.last_history_id + ROLLUP_BATCH_SIZE);
+
+    // the previous check of each entry is included, its state lasts until the first new check
+    let checks = sqlx::query!(
+        r#"
+SELECT
+    status_id AS "status_id!: i64",
+    created AS "created!: NaiveDateTime"
+FROM
+    status_history
+WHERE
+    id > ?
+    AND id <= ?
+UNION ALL
+SELECT
+    status_id,
+    MAX(created)
+FROM
+    status_history
+WHERE
+    id <= ?
+    AND status_id IN (
+        SELECT
+            status_id
+        FROM
+            status_history
+        WHERE
+            id > ?
+            AND id <= ?
+    )
+GROUP BY
+    status_id
+ORDER BY
+    1,
+    2
+"#,
+        state.last_history_id,
+        last_history_id,
+        state.last_history_id,
+        state.last_history_id,
+        last_history_id
+    )
+    .fetch_all(&mut *conn)
+    .await
+    .context("Failed to fetch new checks")?;
+
+    let mut buckets = BTreeSet::new();
+    for entry_checks in checks.chunk_by(|a, b| a.status_id == b.status_id) {
+        let status_id = entry_checks[0].status_id;
+        let max_gap = max_gaps.get(&status_id).copied().unwrap_or(DEFAULT_MAX_GAP);
+        let mut hours = BTreeSet::new();
+        for pair in entry_checks.windows(2) {
+            let (from, to) = (
+                pair[0].created,
+                pair[1].created.min(pair[0].created + max_gap),
+            );
+            let mut hour = bucket_start(RollupPeriod::Hour, from);
+            while hour <= to {
+                hours.insert(hour);
+                hour += bucket_len(RollupPeriod::Hour);
+            }
+        }
+        hours.extend(
+            entry_checks
+                .iter()
+                .map(|c| bucket_start(RollupPeriod::Hour, c.created)),
+        );
+        for hour in hours {
+            for period in [RollupPeriod::Hour, RollupPeriod::Day] {
+                buckets.insert((status_id, period, bucket_start(period, hour)));
+            }
+        }
+    }
+    debug!(buckets = buckets.len(), "Updating rollups");
+    for (status_id, period, start) in buckets {
+        // the stored bucket was computed before its checks were pruned, recomputing loses them
+        if state.pruned_until.is_some_and(|p| start < p)
+            && bucket_exists(&mut *conn, status_id, period, start).await?
+        {
+            continue;
+        }
+        let max_gap = max_gaps.get(&status_id).copied().unwrap_or(DEFAULT_MAX_GAP);
+        update_bucket(&mut *conn, status_id, period, start, max_gap).await?;
+    }
+
+    sqlx::query!(
+        r#"
+UPDATE
+    rollup_state
+SET
+    last_history_id = ?
+WHERE
+    id = 1
+"#,
+        last_history_id
+    )
+    .execute(&mut *conn)
+    .await
+    .context("Failed to update rollup state")?;
+
+    tr.commit().await.context("Failed to commit transaction")?;
+    Ok(last_history_id < max_id)
+}
+
+async fn bucket_exists(
+    conn: &mut SqliteConnection,
+    status_id: i64,
+    period: RollupPeriod,
+    start: NaiveDateTime,
+) -> anyhow::Result<bool> {
+    let bucket = start.format(BUCKET_FORMAT).to_string();
+    let exists = sqlx::query_scalar!(
+        r#"
+SELECT
+    EXISTS (
+        SELECT
+            1
+        FROM
+            status_rollup
+        WHERE
+            status_id = ?
+            AND period = ?
+            AND bucket = ?
+    ) AS "exists!: bool"
+"#,
+        status_id,
+        period,
+        bucket
+    )
+    .fetch_one(&mut *conn)
+    .await
+    .context("Failed to check for a rollup")?;
+    Ok(exists)
+}
+
+async fn update_bucket(
+    conn: &mut SqliteConnection,
+    status_id: i64,
+    period: RollupPeriod,
+    start: NaiveDateTime,
+    max_gap: TimeDelta,
+) -> anyhow::Result<()> {
+    // created is stored as text, so the bounds are compared as text too
+    let from = start.format(BUCKET_FORMAT).to_string();
+    let to = (start + bucket_len(period))
+        .format(BUCKET_FORMAT)
+        .to_string();
+    let checks = sqlx::query!(
+        r#"
+SELECT
+    created AS "created!: NaiveDateTime",
+    state AS "state: CheckState",
+    latency_ms
+FROM
+    status_history
+WHERE
+    status_id = ?
+    AND created >= ?
+    AND created < ?
+ORDER BY
+    created
+"#,
+        status_id,
+        from,
+        to
+    )
+    .fetch_all(&mut *conn)
+    .await
+    .context("Failed to fetch checks of the bucket")?;
+    let previous = sqlx::query!(
+        r#"
+SELECT
+    created AS "created!: NaiveDateTime",
+    state AS "state: CheckState"
+FROM
+    status_history
+WHERE
+    status_id = ?
+    AND created < ?
+ORDER BY
+    created DESC
+LIMIT
+    1
+"#,
+        status_id,
+        from
+    )
+    .fetch_optional(&mut *conn)
+    .await
+    .context("Failed to fetch the check before the bucket")?;
+    let next = sqlx::query!(
+        r#"
+SELECT
+    created AS "created!: NaiveDateTime"
+FROM
+    status_history
+WHERE
+    status_id = ?
+    AND created >= ?
+ORDER BY
+    created
+LIMIT
+    1
+"#,
+        status_id,
+        to
+    )
+    .fetch_optional(&mut *conn)
+    .await
+    .context("Failed to fetch the check after the bucket")?;
+
+    let count = checks.len() as i64;
+    let failures = checks
+        .iter()
+        .filter(|c| c.state == CheckState::Down)
+        .count() as i64;
+    let degraded = checks
+        .iter()
+        .filter(|c| c.state == CheckState::Degraded)
+        .count() as i64;
+    let mut latencies = checks
+        .iter()
+        .filter(|c| c.state != CheckState::Down)
+        .filter_map(|c| c.latency_ms)
+        .collect::<Vec<_>>();
+    latencies.sort_unstable();
+    let latency_min_ms = latencies.first().copied();
+    let latency_max_ms = latencies.last().copied();
+    let latency_avg_ms = (!latencies.is_empty())
+        .then(|| latencies.iter().sum::<i64>() as f64 / latencies.len() as f64);
+    let latency_p95_ms = percentile(&latencies, 95);
+
+    let samples = previous
+        .map(|c| (c.created, c.state))
+        .into_iter()
+        .chain(checks.iter().map(|c| (c.created, c.state)))
+        .collect::<Vec<_>>();
+    let times = state_times(
+        &samples,
+        next.map(|c| c.created),
+        (start, start + bucket_len(period)),
+        max_gap,
+    );
+
+    sqlx::query!(
+        r#"
+INSERT INTO
+    status_rollup (
+        status_id,
+        period,
+        bucket,
+        checks,
+        failures,
+        degraded,
+        latency_min_ms,
+        latency_avg_ms,
+        latency_max_ms,
+        latency_p95_ms,
+        up_ms,
+        degraded_ms,
+        down_ms
+    )
+VALUES
+    (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?) ON CONFLICT DO
+UPDATE
+SET
+    checks = excluded.checks,
+    failures = excluded.failures,
+    degraded = excluded.degraded,
+    latency_min_ms = excluded.latency_min_ms,
+    latency_avg_ms = excluded.latency_avg_ms,
+    latency_max_ms = excluded.latency_max_ms,
+    latency_p95_ms = excluded.latency_p95_ms,
+    up_ms = excluded.up_ms,
+    degraded_ms = excluded.degraded_ms,
+    down_ms = excluded.down_ms
+"#,
+        status_id,
+        period,
+        from,
+        count,
+        failures,
+        degraded,
+        latency_min_ms,
+        latency_avg_ms,
+        latency_max_ms,
+        latency_p95_ms,
+        times.up_ms,
+        times.degraded_ms,
+        times.down_ms
+    )
+    .execute(&mut *conn)
+    .await
+    .with_context(|| format!("Failed to update rollup of {status_id} at {from}"))?;
+    Ok(())
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(super) struct StateTimes {
+    pub up_ms: i64,
+    pub degraded_ms: i64,
+    pub down_ms: i64,
+}
+
+/// Time spent in each state within `bucket`, from checks sorted by time
+///
+/// A check lasts until the next one, at most `max_gap`. The last check is not counted
+/// until `next` is known.
+pub(super) fn state_times(
+    samples: &[(NaiveDateTime, CheckState)],
+    next: Option<NaiveDateTime>,
+    bucket: (NaiveDateTime, NaiveDateTime),
+    max_gap: TimeDelta,
+) -> StateTimes {
+    let mut times = StateTimes::default();
+    for (i, (created, state)) in samples.iter().enumerate() {
+        let Some(end) = samples.get(i + 1).map(|s| s.0).or(next) else {
+            continue;
+        };
+        let start = (*created).max(bucket.0);
+        let end = end.min(*created + max_gap).min(bucket.1);
+        if end <= start {
+            continue;
+        }
+        let ms = (end - start).num_milliseconds();
+        match state {
+            CheckState::Up => times.up_ms += ms,
+            CheckState::Degraded => times.degraded_ms += ms,
+            CheckState::Down => times.down_ms += ms,
+        }
+    }
+    times
+}
+
+/// Nearest-rank percentile of sorted values
+fn percentile(sorted: &[i64], p: usize) -> Option<i64> {
+    if sorted.is_empty() {
+        return None;
+    }
+    let rank = (sorted.len() * p).div_ceil(100).max(1);
+    Some(sorted[rank - 1])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::status_check::tests::memory_db;
+
+    /// Checks, failures, degraded, p95 and the up, degraded and down minutes of a bucket
+    type Bucket = (i64, i64, i64, Option<i64>, i64, i64, i64);
+
+    async fn bucket(db: &SqlitePool, period: &str, bucket: &str) -> Bucket {
+        let (checks, failures, degraded, p95, up_ms, degraded_ms, down_ms) =
+            sqlx::query_as::<_, (i64, i64, i64, Option<i64>, i64, i64, i64)>(
+                r#"
+SELECT
+    checks,
+    failures,
+    degraded,
+    latency_p95_ms,
+    up_ms,
+    degraded_ms,
+    down_ms
+FROM
+    status_rollup
+WHERE
+    status_id = 1
+    AND period = ?
+    AND bucket = ?
+"#,
+            )
+            .bind(period)
+            .bind(bucket)
+            .fetch_one(db)
+            .await
+            .unwrap();
+        let minutes = |ms: i64| ms / 60_000;
+        (
+            checks,
+            failures,
+            degraded,
+            p95,
+            minutes(up_ms),
+            minutes(degraded_ms),
+            minutes(down_ms),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_rollup_batch() {
+        let db = memory_db().await;
+        sqlx::query(
+            r#"
+INSERT INTO status_history (id, status_id, state, latency_ms, created) VALUES
+    (1, 1, 'up', 100, '2026-10-18 23:40:00'),
+    (2, 1, 'degraded', 300, '2026-10-18 23:50:00'),
+    (3, 1, 'down', NULL, '2026-10-19 00:10:00'),
+    (4, 1, 'up', 200, '2026-10-19 00:20:00');
+"#,
+        )
+        .execute(&db)
+        .await
+        .unwrap();
+        let max_gaps = HashMap::from([(1, TimeDelta::minutes(20))]);
+        let last_history_id = || async {
+            sqlx::query_scalar::<_, i64>("SELECT last_history_id FROM rollup_state")
+                .fetch_one(&db)
+                .await
+                .unwrap()
+        };
+
+        assert!(!rollup_batch(&db, &max_gaps).await.unwrap());
+        assert_eq!(last_history_id().await, 4);
+        // the degraded check lasts until the down one, across both boundaries
+        assert_eq!(
+            bucket(&db, "hour", "2026-10-18 23:00:00").await,
+            (2, 0, 1, Some(300), 10, 10, 0)
+        );
+        // the time of the last check is not known yet
+        assert_eq!(
+            bucket(&db, "hour", "2026-10-19 00:00:00").await,
+            (2, 1, 0, Some(200), 0, 10, 10)
+        );
+        assert_eq!(
+            bucket(&db, "day", "2026-10-18 00:00:00").await,
+            (2, 0, 1, Some(300), 10, 10, 0)
+        );
+        assert_eq!(
+            bucket(&db, "day", "2026-10-19 00:00:00").await,
+            (2, 1, 0, Some(200), 0, 10, 10)
+        );
+
+        sqlx::query(
+            "INSERT INTO status_history (id, status_id, state, latency_ms, created) \
+             VALUES (5, 1, 'up', 400, '2026-10-19 00:30:00')",
+        )
+        .execute(&db)
+        .await
+        .unwrap();
+        assert!(!rollup_batch(&db, &max_gaps).await.unwrap());
+        assert_eq!(last_history_id().await, 5);
+        assert_eq!(
+            bucket(&db, "hour", "2026-10-19 00:00:00").await,
+            (3, 1, 0, Some(400), 10, 10, 10)
+        );
+        assert_eq!(
+            bucket(&db, "hour", "2026-10-18 23:00:00").await,
+            (2, 0, 1, Some(300), 10, 10, 0)
+        );
+        // nothing new
+        assert!(!rollup_batch(&db, &max_gaps).await.unwrap());
+    }
+
+    #[test]
+    fn test_percentile() {
+        assert_eq!(percentile(&[], 95), None);
+        assert_eq!(percentile(&[7], 95), Some(7));
+        let values = (1..=100).collect::<Vec<_>>();
+        assert_eq!(percentile(&values, 95), Some(95));
+        assert_eq!(percentile(&values[..20], 95), Some(19));
+    }
+
+    #[test]
+    fn test_state_times() {
+        let at = |h, m| {
+            chrono::NaiveDate::from_ymd_opt(2026, 10, 18)
+                .unwrap()
+                .and_hms_opt(h, m, 0)
+                .unwrap()
+        };
+        let minutes = |m: i64| m * 60 * 1000;
+        let samples = [
+            (at(9, 50), CheckState::Down),
+            (at(10, 20), CheckState::Up),
+            // the server was stopped after this check
+            (at(10, 30), CheckState::Degraded),
+            (at(12, 0), CheckState::Up),
+        ];
+        let times = state_times(
+            &samples,
+            None,
+            (at(10, 0), at(11, 0)),
+            TimeDelta::minutes(20),
+        );
+        assert_eq!(
+            times,
+            StateTimes {
+                up_ms: minutes(10),
+                degraded_ms: minutes(20),
+                down_ms: minutes(10),
+            }
+        );
+    }
+}
@@ -0,0 +1,321 @@
+use std::time::Duration;
+
+use anyhow::Context;
+use chrono::{NaiveDateTime, TimeDelta};
+use sqlx::SqlitePool;
+use tokio::sync::watch;
+use tracing::error;
+
+use super::{
+    rollup::{state_times, BUCKET_FORMAT},
+    Monitor,
+};
+use crate::app::{CheckState, Slo};
+
+/// How often the error budgets are recomputed
+const SLO_INTERVAL: Duration = Duration::from_secs(60);
+/// Burn rate windows and the share of the whole budget they may spend before alerting
+const FAST_BURN_1H: f64 = 0.02;
+const FAST_BURN_6H: f64 = 0.05;
+
+/// Keeps `slo_status` up to date for the entries with an slo
+pub async fn update_slos(db: SqlitePool, monitors: watch::Receiver<Vec<Monitor>>) {
+    let mut interval = tokio::time::interval(SLO_INTERVAL);
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    loop {
+        interval.tick().await;
+        let monitors = monitors.borrow().clone();
+        if let Err(err) = update_once(&db, &monitors).await {
+            error!(?err, "Failed to update error budgets");
+        }
+    }
+}
+
+async fn update_once(db: &SqlitePool, monitors: &[Monitor]) -> anyhow::Result<()> {
+    let ids = monitors
+        .iter()
+        .filter(|m| m.entry.slo.is_some())
+        .map(|m| m.id)
+        .collect::<Vec<_>>();
+    let ids = serde_json::to_string(&ids).context("Failed to serialize ids")?;
+    sqlx::query!(
+        r#"
+DELETE FROM
+    slo_status
+WHERE
+    status_id NOT IN (
+        SELECT
+            value
+        FROM
+            json_each(?)
+    )
+"#,
+        ids
+    )
+    .execute(db)
+    .await
+    .context("Failed to delete removed slos")?;
+
+    for monitor in monitors {
+        if let Some(slo) = &monitor.entry.slo {
+            update_slo(db, monitor, slo)
+                .await
+                .with_context(|| format!("Failed to update slo of {}", monitor.entry.name))?;
+        }
+    }
+    Ok(())
+}
+
+async fn update_slo(db: &SqlitePool, monitor: &Monitor, slo: &Slo) -> anyhow::Result<()> {
+    let now = chrono::Utc::now().naive_utc();
+    let window = TimeDelta::from_std(slo.window()).context("slo window is too long")?;
+
+    // the window is read from the hourly rollups, the raw checks may be pruned already
+    let since = (now - window).format("%Y-%m-%d %H:00:00").to_string();
+    let down_ms = sqlx::query!(
+        r#"
+SELECT
+    COALESCE(SUM(down_ms), 0) AS "down_ms!: i64"
+FROM
+    status_rollup
+WHERE
+    status_id = ?
+    AND period = 'hour'
+    AND bucket >= ?
+"#,
+        monitor.id,
+        since
+    )
+    .fetch_one(db)
+    .await
+    .context("Failed to fetch downtime")?
+    .down_ms;
+    let samples = recent_checks(db, monitor.id, now - TimeDelta::hours(6)).await?;
+    let max_gap = TimeDelta::from_std(monitor.interval * 2).unwrap_or(TimeDelta::MAX);
+    let BudgetStatus {
+        budget_remaining,
+        burn_rate_1h,
+        burn_rate_6h,
+        burning_fast,
+    } = budget_status(slo.target, window, down_ms, &samples, now, max_gap);
+
+    let window_secs = window.num_seconds();
+    sqlx::query!(
+        r#"
+INSERT INTO
+    slo_status (
+        status_id,
+        target,
+        window_secs,
+        down_ms,
+        budget_remaining,
+        burn_rate_1h,
+        burn_rate_6h,
+        burning_fast,
+        updated
+    )
+VALUES
+    (?, ?, ?, ?, ?, ?, ?, ?, CURRENT_TIMESTAMP) ON CONFLICT DO
+UPDATE
+SET
+    target = excluded.target,
+    window_secs = excluded.window_secs,
+    down_ms = excluded.down_ms,
+    budget_remaining = excluded.budget_remaining,
+    burn_rate_1h = excluded.burn_rate_1h,
+    burn_rate_6h = excluded.burn_rate_6h,
+    burning_fast = excluded.burning_fast,
+    updated = excluded.updated
+"#,
+        monitor.id,
+        slo.target,
+        window_secs,
+        down_ms,
+        budget_remaining,
+        burn_rate_1h,
+        burn_rate_6h,
+        burning_fast
+    )
+    .execute(db)
+    .await
+    .context("Failed to update slo status")?;
+    Ok(())
+}
+
+#[derive(Debug, PartialEq)]
+struct BudgetStatus {
+    /// Share of the error budget left, negative once it is overspent
+    budget_remaining: f64,
+    burn_rate_1h: f64,
+    burn_rate_6h: f64,
+    burning_fast: bool,
+}
+
+/// Error budget after `down_ms` of downtime within the window and the burn rates of `samples`
+///
+/// A 100% target has no budget, any downtime overspends it at the highest finite rate.
+fn budget_status(
+    target: f64,
+    window: TimeDelta,
+    down_ms: i64,
+    samples: &[(NaiveDateTime, CheckState)],
+    now: NaiveDateTime,
+    max_gap: TimeDelta,
+) -> BudgetStatus {
+    let allowed = 1.0 - target / 100.0;
+    // times the allowed share of downtime that `down` is
+    let relative = |down: f64| {
+        if allowed > 0.0 {
+            down / allowed
+        } else if down > 0.0 {
+            f64::MAX
+        } else {
+            0.0
+        }
+    };
+    let window_ms = window.num_milliseconds() as f64;
+    let budget_remaining = 1.0 - relative(down_ms as f64 / window_ms);
+
+    let burn_rate = |hours| {
+        let times = state_times(
+            samples,
+            Some(now),
+            (now - TimeDelta::hours(hours), now),
+            max_gap,
+        );
+        let observed = times.up_ms + times.degraded_ms + times.down_ms;
+        if observed == 0 {
+            return 0.0;
+        }
+        relative(times.down_ms as f64 / observed as f64)
+    };
+    let burn_rate_1h = burn_rate(1);
+    let burn_rate_6h = burn_rate(6);
+    // share of the whole budget spent within the burn rate window
+    let spent = |burn_rate: f64, hours: i64| {
+        burn_rate * TimeDelta::hours(hours).num_milliseconds() as f64 / window_ms
+    };
+    BudgetStatus {
+        budget_remaining,
+        burn_rate_1h,
+        burn_rate_6h,
+        burning_fast: spent(burn_rate_1h, 1) >= FAST_BURN_1H
+            || spent(burn_rate_6h, 6) >= FAST_BURN_6H,
+    }
+}
+
+/// Checks since `since` and the one before, sorted by time
+async fn recent_checks(
+    db: &SqlitePool,
+    status_id: i64,
+    since: NaiveDateTime,
+) -> anyhow::Result<Vec<(NaiveDateTime, CheckState)>> {
+    let since = since.format(BUCKET_FORMAT).to_string();
+    let previous = sqlx::query!(
+        r#"
+SELECT
+    created AS "created!: NaiveDateTime",
+    state AS "state: CheckState"
+FROM
+    status_history
+WHERE
+    status_id = ?
+    AND created < ?
+ORDER BY
+    created DESC
+LIMIT
+    1
+"#,
+        status_id,
+        since
+    )
+    .fetch_optional(db)
+    .await
+    .context("Failed to fetch the check before the window")?;
+    let checks = sqlx::query!(
+        r#"
+SELECT
+    created AS "created!: NaiveDateTime",
+    state AS "state: CheckState"
+FROM
+    status_history
+WHERE
+    status_id = ?
+    AND created >= ?
+ORDER BY
+    created
+"#,
+        status_id,
+        since
+    )
+    .fetch_all(db)
+    .await
+    .context("Failed to fetch recent checks")?;
+    Ok(previous
+        .map(|c| (c.created, c.state))
+        .into_iter()
+        .chain(checks.into_iter().map(|c| (c.created, c.state)))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_budget_status() {
+        let now = chrono::NaiveDate::from_ymd_opt(2026, 10, 18)
+            .unwrap()
+            .and_hms_opt(12, 0, 0)
+            .unwrap();
+        let window = TimeDelta::days(30);
+        let max_gap = TimeDelta::minutes(2);
+        // a check every minute for the last 6 hours, down for the last `down` minutes
+        let checks = |down: i64| {
+            (0..=6 * 60)
+                .rev()
+                .map(|m: i64| {
+                    let state = if down > 0 && m <= down {
+                        CheckState::Down
+                    } else {
+                        CheckState::Up
+                    };
+                    (now - TimeDelta::minutes(m), state)
+                })
+                .collect::<Vec<_>>()
+        };
+
+        // nothing observed yet
+        let status = budget_status(99.9, window, 0, &[], now, max_gap);
+        assert_eq!(
+            status,
+            BudgetStatus {
+                budget_remaining: 1.0,
+                burn_rate_1h: 0.0,
+                burn_rate_6h: 0.0,
+                burning_fast: false,
+            }
+        );
+
+        // 99% of 30 days allows 432 minutes of downtime, 30 of them in the last hour
+        let status = budget_status(99.0, window, 30 * 60_000, &checks(30), now, max_gap);
+        assert!((status.budget_remaining - (1.0 - 30.0 / 432.0)).abs() < 1e-9);
+        assert!((status.burn_rate_1h - 50.0).abs() < 1e-9, "{status:?}");
+        assert!(status.burning_fast);
+
+        // a budget that is used up already
+        let status = budget_status(99.0, window, 864 * 60_000, &checks(0), now, max_gap);
+        assert!((status.budget_remaining + 1.0).abs() < 1e-9);
+        assert_eq!(status.burn_rate_6h, 0.0);
+        assert!(!status.burning_fast);
+
+        // a 100% target has no budget
+        let status = budget_status(100.0, window, 0, &checks(0), now, max_gap);
+        assert_eq!(status.budget_remaining, 1.0);
+        assert!(!status.burning_fast);
+        let status = budget_status(100.0, window, 60_000, &checks(1), now, max_gap);
+        assert!(status.budget_remaining.is_finite() && status.budget_remaining < 0.0);
+        assert!(status.burn_rate_1h.is_finite());
+        assert!(status.burning_fast);
+    }
+}
@@ -0,0 +1,116 @@
+use std::time::Duration;
+
+use anyhow::Context;
+use tokio::{net::TcpStream, time::Instant};
+
+use super::{classify_io_error, CheckResult};
+use crate::app::{CheckState, ErrorKind};
+
+/// Target of a `tcp://host:port` monitor
+#[derive(Debug, Clone)]
+pub struct TcpTarget {
+    pub host: String,
+    pub port: u16,
+}
+
+impl TcpTarget {
+    pub fn from_url(url: &url::Url) -> anyhow::Result<Self> {
+        let host = url.host_str().context("tcp url is missing the host")?;
+        let port = url.port().context("tcp url is missing the port")?;
+        Ok(Self {
+            host: host.to_owned(),
+            port,
+        })
+    }
+}
+
+/// Records whether a connection can be opened and how long the connect took
+pub(super) async fn check(target: &TcpTarget, timeout: Duration, result: &mut CheckResult) {
+    let deadline = Instant::now() + timeout;
+    let addrs = match tokio::time::timeout_at(
+        deadline,
+        tokio::net::lookup_host((target.host.as_str(), target.port)),
+    )
+    .await
+    {
+        Ok(Ok(addrs)) => addrs.collect::<Vec<_>>(),
+        Ok(Err(err)) => return result.fail(ErrorKind::Dns, &err),
+        Err(err) => return result.fail(ErrorKind::Timeout, &err),
+    };
+
+    let started = Instant::now();
+    let mut last_err = None;
+    for addr in addrs {
+        match tokio::time::timeout_at(deadline, TcpStream::connect(addr)).await {
+            Ok(Ok(_stream)) => {
+                result.state = CheckState::Up;
+                result.latency_ms = Some(started.elapsed().as_millis() as i64);
+                return;
+            }
+            Ok(Err(err)) => last_err = Some(err),
+            Err(err) => return result.fail(ErrorKind::Timeout, &err),
+        }
+    }
+    match last_err {
+        Some(err) => {
+            let kind = classify_io_error(&err).unwrap_or(ErrorKind::Connect);
+            result.fail(kind, &err)
+        }
+        None => result.fail(
+            ErrorKind::Dns,
+            &std::io::Error::other(format!("{} did not resolve to any address", target.host)),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::status_check::tests::test_monitor;
+
+    async fn check_port(port: u16, timeout: Duration) -> CheckResult {
+        let monitor = test_monitor(&format!("tcp://127.0.0.1:{port}"));
+        let target = TcpTarget::from_url(monitor.url()).unwrap();
+        let mut result = CheckResult::new(&monitor);
+        check(&target, timeout, &mut result).await;
+        result
+    }
+
+    #[tokio::test]
+    async fn test_check() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let result = check_port(port, Duration::from_secs(5)).await;
+        assert_eq!(result.state, CheckState::Up);
+        assert!(result.latency_ms.is_some());
+        assert_eq!(result.error_kind, None);
+
+        drop(listener);
+        let result = check_port(port, Duration::from_secs(5)).await;
+        assert_eq!(result.state, CheckState::Down);
+        assert_eq!(result.error_kind, Some(ErrorKind::ConnectionRefused));
+        assert_eq!(result.latency_ms, None);
+    }
+
+    #[tokio::test]
+    async fn test_check_deadline() {
+        // connections are not answered once the accept queue is full
+        let socket = tokio::net::TcpSocket::new_v4().unwrap();
+        socket.bind("127.0.0.1:0".parse().unwrap()).unwrap();
+        let listener = socket.listen(0).unwrap();
+        let addr = listener.local_addr().unwrap();
+        let mut queued = Vec::new();
+        for _ in 0..16 {
+            match tokio::time::timeout(Duration::from_millis(200), TcpStream::connect(addr)).await {
+                Ok(Ok(stream)) => queued.push(stream),
+                _ => break,
+            }
+        }
+
+        let started = Instant::now();
+        let result = check_port(addr.port(), Duration::from_millis(300)).await;
+        assert_eq!(result.state, CheckState::Down);
+        assert_eq!(result.error_kind, Some(ErrorKind::Timeout));
+        assert!(started.elapsed() < Duration::from_secs(2));
+    }
+}
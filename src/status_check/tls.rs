@@ -0,0 +1,77 @@
+use anyhow::Context;
+use chrono::NaiveDateTime;
+use x509_parser::{extensions::GeneralName, prelude::*};
+
+/// Details of the peer certificate of an https check
+#[derive(Debug, Clone)]
+pub struct CertificateInfo {
+    pub subject: String,
+    pub issuer: String,
+    pub sans: Vec<String>,
+    pub not_before: NaiveDateTime,
+    pub not_after: NaiveDateTime,
+}
+
+impl CertificateInfo {
+    pub fn from_der(der: &[u8]) -> anyhow::Result<Self> {
+        let (_, cert) = parse_x509_certificate(der).context("Failed to parse certificate")?;
+        let validity = cert.validity();
+        let timestamp = |t: &ASN1Time| {
+            chrono::DateTime::from_timestamp(t.timestamp(), 0)
+                .map(|t| t.naive_utc())
+                .context("Certificate validity is out of range")
+        };
+        let sans = cert
+            .subject_alternative_name()
+            .context("Invalid subject alternative name extension")?
+            .map(|ext| {
+                ext.value
+                    .general_names
+                    .iter()
+                    .filter_map(|name| match name {
+                        GeneralName::DNSName(name) => Some(name.to_string()),
+                        GeneralName::IPAddress(ip) => match ip.len() {
+                            4 => <[u8; 4]>::try_from(*ip)
+                                .ok()
+                                .map(|ip| std::net::IpAddr::from(ip).to_string()),
+                            16 => <[u8; 16]>::try_from(*ip)
+                                .ok()
+                                .map(|ip| std::net::IpAddr::from(ip).to_string()),
+                            _ => None,
+                        },
+                        _ => None,
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        Ok(Self {
+            subject: cert.subject().to_string(),
+            issuer: cert.issuer().to_string(),
+            sans,
+            not_before: timestamp(&validity.not_before)?,
+            not_after: timestamp(&validity.not_after)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_der() {
+        let (_, pem) =
+            x509_parser::pem::parse_x509_pem(include_bytes!("testdata/example.pem")).unwrap();
+        let cert = CertificateInfo::from_der(&pem.contents).unwrap();
+        assert_eq!(cert.subject, "CN=example.com");
+        assert_eq!(cert.issuer, "CN=Test CA, O=Uptime");
+        assert_eq!(
+            cert.sans,
+            ["example.com", "www.example.com", "127.0.0.1", "::1"]
+        );
+        assert_eq!(cert.not_before.to_string(), "2026-01-01 00:00:00");
+        assert_eq!(cert.not_after.to_string(), "2027-01-01 00:00:00");
+
+        assert!(CertificateInfo::from_der(b"not a certificate").is_err());
+    }
+}
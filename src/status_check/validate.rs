@@ -0,0 +1,353 @@
+use std::{collections::HashSet, fmt, time::Duration};
+
+use serde::{
+    de::{DeserializeSeed, Deserializer, IgnoredAny, MapAccess, SeqAccess, Visitor},
+    Deserialize as _,
+};
+use serde_derive::Deserialize;
+
+use super::{notify::check_notifiers, Monitor};
+use crate::app::{Config, Entry};
+
+const MIN_RETENTION: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Problem found in a config file, `position` is the line and column starting at 1
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigProblem {
+    pub position: Option<(usize, usize)>,
+    pub message: String,
+}
+
+impl ConfigProblem {
+    fn new(contents: &str, offset: Option<usize>, message: impl ToString) -> Self {
+        Self {
+            position: offset.map(|offset| line_column(contents, offset)),
+            message: message.to_string(),
+        }
+    }
+
+    fn from_toml(contents: &str, err: &toml::de::Error) -> Self {
+        Self::new(contents, err.span().map(|s| s.start), err.message().trim())
+    }
+}
+
+impl fmt::Display for ConfigProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.position {
+            Some((line, column)) => write!(f, "{line}:{column}: {}", self.message),
+            None => f.write_str(&self.message),
+        }
+    }
+}
+
+fn line_column(contents: &str, offset: usize) -> (usize, usize) {
+    let before = &contents[..offset.min(contents.len())];
+    let line = before.matches('\n').count() + 1;
+    let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+    (line, before[line_start..].chars().count() + 1)
+}
+
+/// Checks the whole config and returns every problem found, instead of stopping at the first one
+pub fn validate_config(contents: &str) -> Vec<ConfigProblem> {
+    /// Positions of the parts that are checked beyond parsing
+    #[derive(Deserialize)]
+    struct Spans {
+        #[serde(default)]
+        retention: Option<toml::Spanned<IgnoredAny>>,
+        #[serde(default)]
+        max_concurrency: Option<toml::Spanned<IgnoredAny>>,
+        #[serde(default)]
+        max_concurrency_per_host: Option<toml::Spanned<IgnoredAny>>,
+        #[serde(default)]
+        notifiers: Vec<toml::Spanned<IgnoredAny>>,
+        #[serde(default)]
+        entries: Vec<toml::Spanned<IgnoredAny>>,
+    }
+
+    let spans = match toml::from_str::<Spans>(contents) {
+        Ok(spans) => spans,
+        // syntax errors, nothing else can be checked
+        Err(err) => return vec![ConfigProblem::from_toml(contents, &err)],
+    };
+    let entries = spans.entries;
+
+    let mut problems = Vec::new();
+    // each entry is deserialized on its own so one broken entry does not hide the others
+    let mut parsed = Vec::with_capacity(entries.len());
+    for (index, spanned) in entries.iter().enumerate() {
+        let res =
+            toml::de::Deserializer::parse(contents).and_then(|de| EntryAt(index).deserialize(de));
+        match res {
+            Ok(Some(entry)) => parsed.push((entry, spanned.span().start)),
+            Ok(None) => {}
+            Err(err) => problems.push(ConfigProblem::from_toml(contents, &err)),
+        }
+    }
+
+    let config = match toml::from_str::<Config>(contents) {
+        Ok(config) => config,
+        Err(err) => {
+            let problem = ConfigProblem::from_toml(contents, &err);
+            if !problems.contains(&problem) {
+                problems.push(problem);
+            }
+            // the global settings are still needed to check the valid entries
+            match global_settings(contents) {
+                Ok(config) => config,
+                Err(err) => {
+                    let problem = ConfigProblem::from_toml(contents, &err);
+                    if !problems.contains(&problem) {
+                        problems.push(problem);
+                    }
+                    return problems;
+                }
+            }
+        }
+    };
+
+    // the rollups of the current day are recomputed from the raw checks
+    if config.retention.is_some_and(|r| r < MIN_RETENTION) {
+        problems.push(ConfigProblem::new(
+            contents,
+            spans.retention.map(|s| s.span().start),
+            "retention must be at least one day",
+        ));
+    }
+
+    for (key, value, spanned) in [
+        (
+            "max_concurrency",
+            config.max_concurrency,
+            spans.max_concurrency,
+        ),
+        (
+            "max_concurrency_per_host",
+            config.max_concurrency_per_host,
+            spans.max_concurrency_per_host,
+        ),
+    ] {
+        if value == Some(0) {
+            problems.push(ConfigProblem::new(
+                contents,
+                spanned.map(|s| s.span().start),
+                format!("{key} must be at least 1"),
+            ));
+        }
+    }
+
+    let mut names = HashSet::new();
+    for (notifier, spanned) in config.notifiers.iter().zip(&spans.notifiers) {
+        let offset = Some(spanned.span().start);
+        if !names.insert(&notifier.name) {
+            problems.push(ConfigProblem::new(
+                contents,
+                offset,
+                format!("Duplicate notifier name {:?}", notifier.name),
+            ));
+        }
+        if let Err(err) = check_notifiers(std::slice::from_ref(notifier)) {
+            problems.push(ConfigProblem::new(contents, offset, format!("{err:#}")));
+        }
+    }
+
+    let mut keys = HashSet::new();
+    for (entry, offset) in &parsed {
+        let offset = Some(*offset);
+        if !keys.insert(entry.key()) {
+            problems.push(ConfigProblem::new(
+                contents,
+                offset,
+                format!("Duplicate entry key {:?}", entry.key()),
+            ));
+        }
+        if let Err(err) = Monitor::new(0, entry, &config) {
+            problems.push(ConfigProblem::new(
+                contents,
+                offset,
+                format!("Invalid entry {}: {err:#}", entry.name),
+            ));
+        }
+    }
+    problems
+}
+
+/// Deserializes the config without its entries, keeping the positions of the errors
+fn global_settings(contents: &str) -> Result<Config, toml::de::Error> {
+    let mut table = toml::de::DeTable::parse(contents)?;
+    for (key, value) in table.get_mut().iter_mut() {
+        if key.get_ref() == "entries" {
+            *value.get_mut() = toml::de::DeValue::Array(toml::de::DeArray::new());
+        }
+    }
+    Config::deserialize(toml::de::Deserializer::from(table))
+}
+
+/// Deserializes only the entry at the index, keeping the positions of its errors
+struct EntryAt(usize);
+
+impl<'de> DeserializeSeed<'de> for EntryAt {
+    type Value = Option<Entry>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Option<Entry>, D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de> Visitor<'de> for EntryAt {
+    type Value = Option<Entry>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a config table")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Option<Entry>, A::Error> {
+        let mut entry = None;
+        while let Some(key) = map.next_key::<String>()? {
+            if key == "entries" {
+                entry = map.next_value_seed(EntriesAt(self.0))?;
+            } else {
+                map.next_value::<IgnoredAny>()?;
+            }
+        }
+        Ok(entry)
+    }
+}
+
+struct EntriesAt(usize);
+
+impl<'de> DeserializeSeed<'de> for EntriesAt {
+    type Value = Option<Entry>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Option<Entry>, D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de> Visitor<'de> for EntriesAt {
+    type Value = Option<Entry>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a list of entries")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Option<Entry>, A::Error> {
+        let mut entry = None;
+        for index in 0.. {
+            let done = if index == self.0 {
+                entry = seq.next_element::<Entry>()?;
+                entry.is_none()
+            } else {
+                seq.next_element::<IgnoredAny>()?.is_none()
+            };
+            if done {
+                break;
+            }
+        }
+        Ok(entry)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_reports_every_entry() {
+        let contents = r#"poll_interval = "1s"
+
+[[entries]]
+name = "a"
+public_url = "https://example.com"
+interval = "soon"
+
+[[entries]]
+name = "b"
+public_url = "ftp://example.com"
+
+[[entries]]
+name = "c"
+public_url = "https://example.com"
+retries = -1
+"#;
+        let problems = validate_config(contents);
+        let positions = problems.iter().map(|p| p.position).collect::<Vec<_>>();
+        assert_eq!(positions, vec![Some((6, 12)), Some((15, 11)), Some((8, 1))]);
+        assert!(problems[2].message.contains("ftp"), "{problems:?}");
+
+        assert!(validate_config("entries = [").len() == 1);
+        assert!(validate_config(
+            "[[entries]]\nname = \"a\"\npublic_url = \"https://example.com\"\n"
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn test_validate_rejects_unknown_fields() {
+        let contents = r#"retention = "1h"
+intervall = "1s"
+
+[[entries]]
+name = "a"
+public_url = "https://example.com"
+expect_status = 200
+"#;
+        let problems = validate_config(contents);
+        let positions = problems.iter().map(|p| p.position).collect::<Vec<_>>();
+        assert_eq!(positions, vec![Some((7, 1)), Some((2, 1))], "{problems:?}");
+        assert!(
+            problems[0].message.contains("expect_status"),
+            "{problems:?}"
+        );
+
+        let problems = validate_config(&contents.replace("intervall", "poll_interval"));
+        let positions = problems.iter().map(|p| p.position).collect::<Vec<_>>();
+        assert_eq!(positions, vec![Some((7, 1)), Some((1, 13))], "{problems:?}");
+    }
+
+    #[test]
+    fn test_validate_rejects_zero_concurrency() {
+        let contents = r#"max_concurrency = 0
+max_concurrency_per_host = 0
+
+[[entries]]
+name = "a"
+public_url = "https://example.com"
+"#;
+        let problems = validate_config(contents);
+        let positions = problems.iter().map(|p| p.position).collect::<Vec<_>>();
+        assert_eq!(
+            positions,
+            vec![Some((1, 19)), Some((2, 28))],
+            "{problems:?}"
+        );
+        assert!(
+            problems[0].message.starts_with("max_concurrency "),
+            "{problems:?}"
+        );
+        assert!(
+            problems[1].message.starts_with("max_concurrency_per_host "),
+            "{problems:?}"
+        );
+        assert!(validate_config(&contents.replace(" 0", " 1")).is_empty());
+    }
+
+    #[test]
+    fn test_validate_does_not_read_secrets() {
+        let config = |source: &str| {
+            format!(
+                r#"notifiers = [
+  {{ name = "mail", type = "smtp", host = "localhost", username = "u", {source}from = "a@example.com", to = ["b@example.com"] }},
+]
+
+[[entries]]
+name = "a"
+public_url = "https://example.com"
+"#
+            )
+        };
+        assert!(validate_config(&config(r#"password_env = "UPTIME_TEST_UNSET", "#)).is_empty());
+        let problems = validate_config(&config(""));
+        assert_eq!(problems.len(), 1, "{problems:?}");
+        assert!(problems[0].message.contains("password_env"), "{problems:?}");
+    }
+}